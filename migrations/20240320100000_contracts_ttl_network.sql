@@ -0,0 +1,5 @@
+-- Scope the contracts to the network profile they live on
+-- Existing rows belong to the "default" profile (rename them if NETWORKS is set)
+ALTER TABLE contracts_ttl ADD COLUMN IF NOT EXISTS network VARCHAR NOT NULL DEFAULT 'default';
+ALTER TABLE contracts_ttl DROP CONSTRAINT IF EXISTS contracts_ttl_pkey;
+ALTER TABLE contracts_ttl ADD PRIMARY KEY (network, contract_id);
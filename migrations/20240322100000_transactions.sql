@@ -0,0 +1,16 @@
+-- Transactions submitted by the API on behalf of the users, tracked until they're final
+CREATE TABLE IF NOT EXISTS transactions (
+  hash VARCHAR PRIMARY KEY NOT NULL,
+  network VARCHAR NOT NULL,
+  kind VARCHAR NOT NULL,
+  source_account VARCHAR NOT NULL,
+  status VARCHAR NOT NULL,
+  envelope_xdr TEXT NOT NULL,
+  ledger INT,
+  result_xdr TEXT,
+  error TEXT,
+  -- Unix timestamps (seconds)
+  created_at BIGINT NOT NULL,
+  updated_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS transactions_network_source_account ON transactions (network, source_account);
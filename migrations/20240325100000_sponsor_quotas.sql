@@ -0,0 +1,11 @@
+-- Fee-bump sponsorship quota of each user account
+-- Rows are created with the network defaults on the first sponsored transaction, update the max_* columns to change them
+CREATE TABLE IF NOT EXISTS sponsor_quotas (
+  network VARCHAR NOT NULL,
+  account VARCHAR NOT NULL,
+  max_transactions INT NOT NULL,
+  max_fee BIGINT NOT NULL,
+  used_transactions INT NOT NULL DEFAULT 0,
+  used_fee BIGINT NOT NULL DEFAULT 0,
+  PRIMARY KEY (network, account)
+);
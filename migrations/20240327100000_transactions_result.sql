@@ -0,0 +1,2 @@
+-- Decoded transaction result (JSON of the TransactionResult XDR)
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS result JSONB;
@@ -0,0 +1,2 @@
+-- Fee (in stroops) charged by the network, to reconcile the spend of the service accounts
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS fee_charged BIGINT;
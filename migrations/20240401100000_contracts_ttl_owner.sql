@@ -0,0 +1,3 @@
+-- Account or contract allowed to change the settings of the contract
+-- Existing rows have no owner until the deployer (or a signed owner) updates them
+ALTER TABLE contracts_ttl ADD COLUMN IF NOT EXISTS owner VARCHAR;
@@ -0,0 +1,4 @@
+-- Last ledger at which live_until_ttl was read from the chain
+ALTER TABLE contracts_ttl ADD COLUMN IF NOT EXISTS checked_at_ledger INT;
+-- The stored live_until_ttl differed from the chain at the last check
+ALTER TABLE contracts_ttl ADD COLUMN IF NOT EXISTS out_of_sync BOOLEAN NOT NULL DEFAULT FALSE;
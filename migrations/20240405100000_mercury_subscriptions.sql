@@ -0,0 +1,16 @@
+-- Subscriptions made to Mercury through the API
+CREATE TABLE IF NOT EXISTS mercury_subscriptions (
+  id SERIAL PRIMARY KEY,
+  network VARCHAR NOT NULL,
+  -- expiration or event
+  kind VARCHAR NOT NULL,
+  contract_id VARCHAR NOT NULL,
+  -- Hashed LedgerKey XDR of the expiration subscriptions
+  hash_xdr VARCHAR,
+  -- Topics (base64 XDR) filtering the event subscriptions
+  topics VARCHAR[] NOT NULL DEFAULT '{}',
+  mercury_response TEXT NOT NULL,
+  -- Unix timestamp (seconds)
+  created_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS mercury_subscriptions_network ON mercury_subscriptions (network, contract_id);
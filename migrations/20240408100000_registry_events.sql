@@ -0,0 +1,23 @@
+-- Decoded Publish, Deploy and Claim events of the registry, indexed for /search
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+CREATE TABLE IF NOT EXISTS registry_events (
+  network VARCHAR NOT NULL,
+  -- Hex sha256 of the kind, transaction hash and data of the event
+  event_id VARCHAR NOT NULL,
+  -- publish, deploy or claim
+  kind VARCHAR NOT NULL,
+  -- Published name of a publish, deployed name of a deploy or claim
+  name VARCHAR,
+  published_name VARCHAR,
+  -- Author, deployer or claimer
+  actor VARCHAR,
+  contract_id VARCHAR,
+  -- Hex hash of the published wasm
+  wasm_hash VARCHAR,
+  ledger BIGINT,
+  tx_hash VARCHAR,
+  -- Lowercase names, addresses and hash matched by /search
+  search_text TEXT NOT NULL,
+  PRIMARY KEY (network, event_id)
+);
+CREATE INDEX IF NOT EXISTS registry_events_search ON registry_events USING GIN (search_text gin_trgm_ops);
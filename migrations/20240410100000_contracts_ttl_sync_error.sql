@@ -0,0 +1,2 @@
+-- Why the live until ledger couldn't be checked at the last check (archived or missing contract, RPC error)
+ALTER TABLE contracts_ttl ADD COLUMN IF NOT EXISTS sync_error TEXT;
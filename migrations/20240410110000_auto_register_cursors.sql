@@ -0,0 +1,6 @@
+-- Last ledger whose Deploy events were registered into contracts_ttl, per network
+-- Deploy events up to it aren't registered again, so that deleted contracts stay deleted
+CREATE TABLE IF NOT EXISTS auto_register_cursors (
+  network VARCHAR PRIMARY KEY,
+  last_ledger BIGINT NOT NULL
+);
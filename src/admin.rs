@@ -0,0 +1,41 @@
+use crate::AppState;
+use crate::error::MyError;
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header::AUTHORIZATION, request::Parts},
+};
+use sha2::{Sha256, Digest};
+use std::sync::Arc;
+
+// Axum extractor guarding the admin routes: Authorization: Bearer <ADMIN_TOKEN>
+// The admin routes are closed when ADMIN_TOKEN isn't set
+pub struct AdminAuth;
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for AdminAuth {
+    type Rejection = MyError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+
+        let Some(admin_token) = &state.admin_token else {
+            return Err(MyError::Unauthorized("ADMIN_TOKEN is not set".to_string()));
+        };
+
+        let bearer = parts.headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        match bearer {
+            Some(token) if same_token(token, admin_token) => Ok(AdminAuth),
+            Some(_) => Err(MyError::Unauthorized("invalid admin token".to_string())),
+            None => Err(MyError::Unauthorized("missing admin bearer token".to_string())),
+        }
+    }
+}
+
+// Compare the digests so the comparison time doesn't depend on the matching prefix
+fn same_token(token: &str, admin_token: &str) -> bool {
+    Sha256::digest(token.as_bytes()) == Sha256::digest(admin_token.as_bytes())
+}
@@ -0,0 +1,234 @@
+use crate::admin::AdminAuth;
+use crate::error::MyError;
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
+use axum::Json;
+use serde::Serialize;
+use serde_json::json;
+use soroban_cli::rpc::Client;
+use std::sync::Arc;
+use stellar_xdr::curr::{AccountEntry, AccountEntryExt, AccountEntryExtensionV1Ext};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::time::{Duration, sleep};
+
+// Base reserve (in stroops) of the network, 0.5 XLM
+const BASE_RESERVE: i64 = 5_000_000;
+
+// Balance of the accounts paying for the service transactions
+#[derive(Serialize, Clone, Default)]
+pub struct BalanceSnapshot {
+    // Balance (in stroops) of every account of the signer pool
+    pub accounts: Vec<AccountBalance>,
+    pub total_balance: i64,
+    // Total balance minus the minimum balances and selling liabilities of the accounts
+    pub total_spendable: i64,
+    // Unix timestamp of the last successful check
+    pub checked_at: u64,
+    pub low_funds: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct AccountBalance {
+    pub account: String,
+    pub balance: i64,
+    pub spendable: i64,
+}
+
+// Handler Response
+#[derive(Serialize)]
+pub struct SourceAccountStatus {
+    pub network: String,
+    pub source_account: String,
+    pub alert_floor: i64,
+    pub manual_bump_reserve: i64,
+    pub manual_bumps_allowed: bool,
+    #[serde(flatten)]
+    pub snapshot: BalanceSnapshot,
+}
+
+// Function to load the balance of the signer pool accounts every 5 minutes
+// Raise an alert (error log + optional webhook) when the total goes under the alert floor
+pub async fn balance_monitor_cron_job(network: Arc<NetworkProfile>) {
+
+    tokio::spawn(async move {
+
+        loop {
+
+            tracing::debug!("Checking source account balance of network {}", network.name);
+
+            match load_balances(&network).await {
+
+                Ok(accounts) => {
+                    let total_balance = accounts.iter().map(|account| account.balance).sum();
+                    let total_spendable = accounts.iter().map(|account| account.spendable).sum();
+                    let low_funds = total_spendable < network.balance_alert_floor;
+
+                    let was_low_funds = network.balance.lock().unwrap().low_funds;
+                    if low_funds && !was_low_funds {
+                        raise_low_funds_alert(&network, total_spendable).await;
+                    }
+
+                    *network.balance.lock().unwrap() = BalanceSnapshot {
+                        accounts,
+                        total_balance,
+                        total_spendable,
+                        checked_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                        low_funds,
+                    };
+                },
+
+                Err(e) => {
+                    tracing::error!("Error while loading source account balance of network {}: {}", network.name, e);
+                }
+            };
+
+            sleep(Duration::from_secs(5 * 60)).await;
+        }
+    });
+}
+
+async fn load_balances(network: &NetworkProfile) -> Result<Vec<AccountBalance>, MyError> {
+
+    let client = Client::new(&network.rpc_url)?;
+
+    let mut accounts = Vec::new();
+    for account in network.signer_pool.public_keys() {
+        let account_entry = client.get_account(account).await?;
+        accounts.push(AccountBalance {
+            account: account.clone(),
+            balance: account_entry.balance,
+            spendable: spendable_balance(&account_entry),
+        });
+    }
+
+    Ok(accounts)
+}
+
+// Balance the account can spend: the minimum balance (base reserves) and selling liabilities are locked
+pub fn spendable_balance(account: &AccountEntry) -> i64 {
+
+    let (selling_liabilities, num_sponsoring, num_sponsored) = match &account.ext {
+        AccountEntryExt::V0 => (0, 0, 0),
+        AccountEntryExt::V1(v1) => match &v1.ext {
+            AccountEntryExtensionV1Ext::V0 => (v1.liabilities.selling, 0, 0),
+            AccountEntryExtensionV1Ext::V2(v2) => (v1.liabilities.selling, v2.num_sponsoring, v2.num_sponsored),
+        },
+    };
+
+    let reserves = 2 + i64::from(account.num_sub_entries) + i64::from(num_sponsoring) - i64::from(num_sponsored);
+    let minimum_balance = reserves * BASE_RESERVE;
+
+    (account.balance - minimum_balance - selling_liabilities).max(0)
+}
+
+async fn raise_low_funds_alert(network: &NetworkProfile, total_spendable: i64) {
+
+    tracing::error!(
+        "LOW FUNDS ALERT: source accounts of network {} can spend {} stroops, under the floor of {} stroops",
+        network.name,
+        total_spendable,
+        network.balance_alert_floor
+    );
+
+    if let Some(webhook_url) = &network.alert_webhook_url {
+        let alert = json!({
+            "text": format!(
+                "SmartDeploy API: source accounts of network {} can spend {} XLM, under the floor of {} XLM",
+                network.name,
+                total_spendable as f64 / 10_000_000.0,
+                network.balance_alert_floor as f64 / 10_000_000.0
+            )
+        });
+        if let Err(e) = reqwest::Client::new().post(webhook_url).json(&alert).send().await {
+            tracing::error!("Error while sending low funds alert: {:?}", e);
+        }
+    }
+}
+
+// Refuse the manual (non-essential) bumps once the funds are nearly exhausted,
+// leaving what's left to the auto-bump worker
+pub fn check_manual_bump_funds(network: &NetworkProfile) -> Result<(), MyError> {
+
+    let snapshot = network.balance.lock().unwrap();
+
+    // Balance not loaded yet, the funds may already be exhausted
+    if snapshot.checked_at == 0 {
+        return Err(MyError::BalanceNotLoaded(network.name.clone()));
+    }
+
+    if snapshot.total_spendable < network.manual_bump_reserve {
+        return Err(MyError::InsufficientFunds(snapshot.total_spendable));
+    }
+
+    Ok(())
+}
+
+// Admin handler to expose the balance of the accounts paying for the service transactions
+pub async fn get_source_account_balance(
+    _admin: AdminAuth,
+    SelectedNetwork(network): SelectedNetwork,
+) -> Result<Json<SourceAccountStatus>, MyError> {
+
+    let snapshot = network.balance.lock().unwrap().clone();
+
+    Ok(Json(SourceAccountStatus {
+        network: network.name.clone(),
+        source_account: network.signer_pool.public_keys()[0].clone(),
+        alert_floor: network.balance_alert_floor,
+        manual_bump_reserve: network.manual_bump_reserve,
+        manual_bumps_allowed: check_manual_bump_funds(&network).is_ok(),
+        snapshot,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stellar_xdr::curr::{
+        AccountEntryExtensionV1, AccountEntryExtensionV2, AccountEntryExtensionV2Ext, AccountId,
+        Liabilities, PublicKey, SequenceNumber, String32, Thresholds, Uint256, VecM,
+    };
+
+    fn account(balance: i64, num_sub_entries: u32, ext: AccountEntryExt) -> AccountEntry {
+        AccountEntry {
+            account_id: AccountId(PublicKey::PublicKeyTypeEd25519(Uint256([0; 32]))),
+            balance,
+            seq_num: SequenceNumber(1),
+            num_sub_entries,
+            inflation_dest: None,
+            flags: 0,
+            home_domain: String32::default(),
+            thresholds: Thresholds([1, 0, 0, 0]),
+            signers: VecM::default(),
+            ext,
+        }
+    }
+
+    #[test]
+    fn spendable_balance_without_extension() {
+        // 2 base reserves + 1 sub entry
+        let account = account(100_000_000, 1, AccountEntryExt::V0);
+        assert_eq!(spendable_balance(&account), 100_000_000 - 3 * BASE_RESERVE);
+    }
+
+    #[test]
+    fn spendable_balance_with_liabilities_and_sponsorships() {
+        let ext = AccountEntryExt::V1(AccountEntryExtensionV1 {
+            liabilities: Liabilities { buying: 0, selling: 10_000_000 },
+            ext: AccountEntryExtensionV1Ext::V2(AccountEntryExtensionV2 {
+                num_sponsored: 1,
+                num_sponsoring: 3,
+                signer_sponsoring_i_ds: VecM::default(),
+                ext: AccountEntryExtensionV2Ext::V0,
+            }),
+        });
+        let account = account(100_000_000, 0, ext);
+        // 2 base reserves + 3 sponsoring - 1 sponsored
+        assert_eq!(spendable_balance(&account), 100_000_000 - 4 * BASE_RESERVE - 10_000_000);
+    }
+
+    #[test]
+    fn spendable_balance_is_never_negative() {
+        let account = account(BASE_RESERVE, 0, AccountEntryExt::V0);
+        assert_eq!(spendable_balance(&account), 0);
+    }
+}
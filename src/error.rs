@@ -1,4 +1,5 @@
 use axum::response::{IntoResponse, Response};
+use std::fmt;
 
 pub enum MyError {
     ReqwestError(reqwest::Error),
@@ -17,6 +18,25 @@ pub enum MyError {
     SqlxError(sqlx::Error),
     InvalidClaimEventData,
     HashRetrievalFailed,
+    LedgerEntryNotFound,
+    ConflictingLedgerKeys,
+    ContractArchived(String),
+    LedgerEntryArchived(String),
+    UnknownNetwork(String),
+    InsufficientFunds(i64),
+    StringToAccountConversionFailed(String, stellar_strkey::DecodeError),
+    UnsupportedTransaction(String),
+    InvalidSignature(String),
+    RestoreRequired,
+    JsonRpcError(String),
+    TransactionNotFound(String),
+    SponsorQuotaExceeded(String),
+    RegistryNotConfigured(String),
+    InvalidArgument(String),
+    TransactionFailed(String, String),
+    NotOwner(String, String),
+    Unauthorized(String),
+    BalanceNotLoaded(String),
 }
 
 // Convert soroban_cli::rpc::Error towards MyError::RpcError
@@ -54,10 +74,10 @@ impl From<sqlx::Error> for MyError {
     }
 }
 
-// Integrate Error into axum response to use it as a return type in axum handlers 
-impl IntoResponse for MyError {
-    fn into_response(self) -> Response {
-        let body = match self {
+// Human readable error message, used as the response body and in the logs
+impl fmt::Display for MyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
             MyError::ReqwestError(reqwest_error) => format!("Reqwest Error: {}", reqwest_error),
             MyError::BadResponseStatus => "Bad Response Status: response status not included between 200 and 300 (excluded).\nResponse not sent".to_string(),
             MyError::EmptyData => "Empty data: Mercury database returns an empty field data".to_string(),
@@ -73,9 +93,35 @@ impl IntoResponse for MyError {
             MyError::RpcError(rpc_error) => format!("Failed to communicate with RPC: {}", rpc_error),
             MyError::SqlxError(sqlx_error) => format!("Failed to communicate with postgres database: {}", sqlx_error),
             MyError::InvalidClaimEventData => "Invalid claim event data: contract_id not found".to_string(),
-            MyError::HashRetrievalFailed => "Hash retrieval failed: the hash of the claim contract can't be found".to_string(),
+            MyError::HashRetrievalFailed => "Hash retrieval failed: the ledger entry is not a wasm contract instance".to_string(),
+            MyError::LedgerEntryNotFound => "Ledger entry not found: the entry doesn't exist or has expired".to_string(),
+            MyError::ConflictingLedgerKeys => "Conflicting ledger keys: use either key or key_xdr, not both".to_string(),
+            MyError::ContractArchived(contract_id) => format!("Contract archived: the instance or the code of {} has expired and must be restored first", contract_id),
+            MyError::LedgerEntryArchived(key_xdr) => format!("Ledger entry archived: the entry {} has expired and must be restored first", key_xdr),
+            MyError::UnknownNetwork(network) => format!("Unknown network: {} is not a configured network profile", network),
+            MyError::InsufficientFunds(balance) => format!("Insufficient funds: the source accounts can spend {} stroops, manual bumps are paused to keep the rest for automatic bumps", balance),
+            MyError::StringToAccountConversionFailed(address, decode_error) => format!("Failed to convert String {:#?} into Account: {:#?}", address, decode_error),
+            MyError::UnsupportedTransaction(reason) => format!("Unsupported transaction: {}", reason),
+            MyError::InvalidSignature(account) => format!("Invalid signature: the transaction isn't signed by {}", account),
+            MyError::RestoreRequired => "Restore required: some ledger entries of the transaction are archived and must be restored first".to_string(),
+            MyError::JsonRpcError(message) => format!("RPC returned an error: {}", message),
+            MyError::TransactionNotFound(hash) => format!("Transaction not found: {} hasn't been submitted through the API", hash),
+            MyError::SponsorQuotaExceeded(account) => format!("Sponsor quota exceeded: {} has used all its sponsored transactions or fees", account),
+            MyError::RegistryNotConfigured(network) => format!("Registry not configured: SMARTDEPLOY_CONTRACT_ID isn't set for network {}", network),
+            MyError::InvalidArgument(reason) => format!("Invalid argument: {}", reason),
+            MyError::TransactionFailed(hash, error) => format!("Transaction failed: {} {}", hash, error),
+            MyError::NotOwner(contract_id, reason) => format!("Not the owner of {}: {}", contract_id, reason),
+            MyError::Unauthorized(reason) => format!("Unauthorized: {}", reason),
+            MyError::BalanceNotLoaded(network) => format!("The balance of the source accounts of network {} isn't loaded yet, manual bumps are paused", network),
         };
 
-        body.into_response()
+        write!(f, "{}", message)
+    }
+}
+
+// Integrate Error into axum response to use it as a return type in axum handlers 
+impl IntoResponse for MyError {
+    fn into_response(self) -> Response {
+        self.to_string().into_response()
     }
 }
\ No newline at end of file
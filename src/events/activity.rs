@@ -0,0 +1,174 @@
+use crate::AppState;
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
+use crate::error::MyError;
+use crate::events::query_events::registry_events;
+use crate::expiration::footprint::{EXTEND_KIND, RESTORE_KIND};
+use crate::events::event_data::{address_to_strkey, event_address, event_string, RegistryEvent};
+use crate::transactions::envelope::decode_envelope;
+use crate::transactions::relay::RELAY_BUMP_KIND;
+use crate::transactions::submit::{TrackedTransaction, STATUS_SUCCESS};
+use axum::{
+    extract::{Query, State},
+    Json
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{
+    LedgerKey, LedgerKeyContractData, TransactionEnvelope, TransactionExt,
+};
+
+// 50 entries per page by default, 200 at most
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 200;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ActivityKind {
+    Publish,
+    Deploy,
+    Claim,
+    Bump,
+    Restore,
+}
+
+impl From<RegistryEvent> for ActivityKind {
+    fn from(registry_event: RegistryEvent) -> Self {
+        match registry_event {
+            RegistryEvent::Publish => ActivityKind::Publish,
+            RegistryEvent::Deploy => ActivityKind::Deploy,
+            RegistryEvent::Claim => ActivityKind::Claim,
+        }
+    }
+}
+
+// Query parameters of the activity handler
+#[derive(Deserialize)]
+pub struct ActivityQuery {
+    // Only this kind of activity
+    pub kind: Option<ActivityKind>,
+    // Account behind the activity (G...)
+    pub actor: Option<String>,
+    // Deployed contract (C...)
+    pub contract: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+// Handler Response: entry of the activity feed
+#[derive(Serialize, Clone)]
+pub struct Activity {
+    pub kind: ActivityKind,
+    // Author, deployer, claimer or source of the bump
+    pub actor: Option<String>,
+    pub contract: Option<String>,
+    // Published or deployed name
+    pub name: Option<String>,
+    pub ledger: Option<i64>,
+    // Unix timestamp
+    pub timestamp: Option<i64>,
+    pub tx_hash: Option<String>,
+}
+
+// Axum Handler to get the Publish, Deploy, Claim and bump activity, most recent first
+pub async fn get_activity(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Query(activity_query): Query<ActivityQuery>,
+) -> Result<Json<Vec<Activity>>, MyError> {
+
+    let mut activity = registry_activity(&network).await?;
+    activity.extend(bump_activity(&state.ttl_pool, &network).await?);
+
+    activity.retain(|entry| {
+        activity_query.kind.is_none_or(|kind| entry.kind == kind)
+            && activity_query.actor.as_ref().is_none_or(|actor| entry.actor.as_ref() == Some(actor))
+            && activity_query.contract.as_ref().is_none_or(|contract| entry.contract.as_ref() == Some(contract))
+    });
+
+    // Most recent first, the entries without ledger last
+    activity.sort_by(|a, b| b.ledger.cmp(&a.ledger).then(b.timestamp.cmp(&a.timestamp)));
+
+    let limit = activity_query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let page = activity
+        .into_iter()
+        .skip(activity_query.offset.unwrap_or(0))
+        .take(limit)
+        .collect();
+
+    Ok(Json(page))
+}
+
+// Publish, Deploy and Claim events of the SmartDeploy registry
+pub async fn registry_activity(network: &NetworkProfile) -> Result<Vec<Activity>, MyError> {
+
+    let mut activity = Vec::new();
+
+    for registry_event in RegistryEvent::ALL {
+
+        let events = registry_events(network, registry_event.topic()).await?;
+
+        activity.extend(events.into_iter().map(|event| Activity {
+            kind: registry_event.into(),
+            actor: event_address(&event.data, registry_event.actor_field()),
+            contract: event_address(&event.data, "contract_id"),
+            name: event_string(&event.data, registry_event.name_field()),
+            ledger: event.ledger,
+            timestamp: event.close_time,
+            tx_hash: event.tx_hash,
+        }));
+    }
+
+    Ok(activity)
+}
+
+// Successful bump and restore transactions submitted through the API
+pub async fn bump_activity(pool: &PgPool, network: &NetworkProfile) -> Result<Vec<Activity>, MyError> {
+
+    let transactions = sqlx::query_as::<_, TrackedTransaction>("SELECT * FROM transactions
+                                        WHERE network = $1 AND status = $2 AND kind IN ($3, $4, $5)
+                                    ")
+                                    .bind(&network.name)
+                                    .bind(STATUS_SUCCESS)
+                                    .bind(EXTEND_KIND)
+                                    .bind(RELAY_BUMP_KIND)
+                                    .bind(RESTORE_KIND)
+                                    .fetch_all(pool)
+                                    .await?;
+
+    let activity = transactions
+        .into_iter()
+        .map(|transaction| Activity {
+            kind: if transaction.kind == RESTORE_KIND { ActivityKind::Restore } else { ActivityKind::Bump },
+            actor: Some(transaction.source_account),
+            contract: footprint_contract(&transaction.envelope_xdr),
+            name: None,
+            ledger: transaction.ledger.map(i64::from),
+            timestamp: Some(transaction.updated_at),
+            tx_hash: Some(transaction.hash),
+        })
+        .collect();
+
+    Ok(activity)
+}
+
+// First contract whose data is in the footprint of the transaction
+fn footprint_contract(envelope_xdr: &str) -> Option<String> {
+
+    let TransactionEnvelope::Tx(envelope) = decode_envelope(envelope_xdr).ok()? else {
+        return None;
+    };
+    let TransactionExt::V1(soroban_data) = &envelope.tx.ext else {
+        return None;
+    };
+
+    let footprint = &soroban_data.resources.footprint;
+    footprint.read_only
+        .iter()
+        .chain(footprint.read_write.iter())
+        .find_map(|key| match key {
+            LedgerKey::ContractData(LedgerKeyContractData { contract, .. }) => Some(address_to_strkey(contract)),
+            _ => None,
+        })
+}
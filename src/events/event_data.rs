@@ -0,0 +1,131 @@
+use std::str::FromStr;
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{AccountId, PublicKey, ScAddress, ScMap, ScSymbol, ScVal, StringM, Uint256};
+
+// Events emitted by the SmartDeploy registry, decoded the same way by the activity feed, the stats and the search index
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RegistryEvent {
+    Publish,
+    Deploy,
+    Claim,
+}
+
+impl RegistryEvent {
+    pub const ALL: [RegistryEvent; 3] = [RegistryEvent::Publish, RegistryEvent::Deploy, RegistryEvent::Claim];
+
+    // First topic of the event
+    pub fn topic(self) -> &'static str {
+        match self {
+            RegistryEvent::Publish => "Publish",
+            RegistryEvent::Deploy => "Deploy",
+            RegistryEvent::Claim => "Claim",
+        }
+    }
+
+    // Data field holding the author, deployer or claimer
+    pub fn actor_field(self) -> &'static str {
+        match self {
+            RegistryEvent::Publish => "author",
+            RegistryEvent::Deploy => "deployer",
+            RegistryEvent::Claim => "claimer",
+        }
+    }
+
+    // Data field holding the published name of a publish, the deployed name of a deploy or claim
+    pub fn name_field(self) -> &'static str {
+        match self {
+            RegistryEvent::Publish => "published_name",
+            RegistryEvent::Deploy | RegistryEvent::Claim => "deployed_name",
+        }
+    }
+}
+
+// Field of a decoded event data (a map keyed by symbols)
+fn event_field<'a>(event: &'a ScVal, field: &str) -> Option<&'a ScVal> {
+
+    let ScVal::Map(Some(ScMap(entries))) = event else {
+        return None;
+    };
+
+    let key = ScVal::Symbol(ScSymbol(StringM::from_str(field).ok()?));
+    entries.iter().find(|entry| entry.key == key).map(|entry| &entry.val)
+}
+
+// String or Symbol field of a decoded event data
+pub fn event_string(event: &ScVal, field: &str) -> Option<String> {
+    match event_field(event, field)? {
+        ScVal::String(string) => Some(string.to_string()),
+        ScVal::Symbol(symbol) => Some(symbol.to_string()),
+        _ => None,
+    }
+}
+
+// Address field of a decoded event data, as a strkey
+pub fn event_address(event: &ScVal, field: &str) -> Option<String> {
+    match event_field(event, field)? {
+        ScVal::Address(address) => Some(address_to_strkey(address)),
+        _ => None,
+    }
+}
+
+// Bytes field of a decoded event data
+pub fn event_bytes<'a>(event: &'a ScVal, field: &str) -> Option<&'a [u8]> {
+    match event_field(event, field)? {
+        ScVal::Bytes(bytes) => Some(bytes.as_slice()),
+        _ => None,
+    }
+}
+
+pub fn address_to_strkey(address: &ScAddress) -> String {
+    match address {
+        ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(key)))) => {
+            stellar_strkey::ed25519::PublicKey(*key).to_string()
+        },
+        ScAddress::Contract(hash) => stellar_strkey::Contract(hash.0).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::stellar_xdr::curr::{BytesM, Hash, ScBytes, ScMapEntry, ScString};
+
+    fn symbol(name: &str) -> ScVal {
+        ScVal::Symbol(ScSymbol(StringM::from_str(name).unwrap()))
+    }
+
+    fn deploy_data() -> ScVal {
+        let entries = vec![
+            ScMapEntry { key: symbol("deployed_name"), val: ScVal::String(ScString(StringM::from_str("counter").unwrap())) },
+            ScMapEntry { key: symbol("published_name"), val: symbol("counter_v1") },
+            ScMapEntry { key: symbol("contract_id"), val: ScVal::Address(ScAddress::Contract(Hash([1; 32]))) },
+            ScMapEntry {
+                key: symbol("deployer"),
+                val: ScVal::Address(ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256([2; 32]))))),
+            },
+            ScMapEntry { key: symbol("hash"), val: ScVal::Bytes(ScBytes(BytesM::try_from(vec![3u8; 32]).unwrap())) },
+        ];
+        ScVal::Map(Some(ScMap(entries.try_into().unwrap())))
+    }
+
+    #[test]
+    fn reads_typed_fields() {
+        let data = deploy_data();
+
+        assert_eq!(event_string(&data, "deployed_name").as_deref(), Some("counter"));
+        assert_eq!(event_string(&data, "published_name").as_deref(), Some("counter_v1"));
+        assert_eq!(event_address(&data, "contract_id"), Some(stellar_strkey::Contract([1; 32]).to_string()));
+        assert_eq!(event_address(&data, "deployer"), Some(stellar_strkey::ed25519::PublicKey([2; 32]).to_string()));
+        assert_eq!(event_bytes(&data, "hash"), Some([3u8; 32].as_slice()));
+    }
+
+    #[test]
+    fn missing_or_mistyped_fields_are_none() {
+        let data = deploy_data();
+
+        assert_eq!(event_string(&data, "author"), None);
+        assert_eq!(event_address(&data, "deployed_name"), None);
+        assert_eq!(event_bytes(&data, "contract_id"), None);
+        assert_eq!(event_string(&ScVal::Void, "deployed_name"), None);
+    }
+}
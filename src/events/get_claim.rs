@@ -1,8 +1,7 @@
-use crate::AppState;
+use crate::network_profile::SelectedNetwork;
 use crate::error::MyError;
-use std::{str::FromStr, sync::Arc};
+use std::str::FromStr;
 use axum::{ 
-    extract::State, 
     response::{IntoResponse, Response},
     Json 
 };
@@ -41,7 +40,7 @@ impl IntoResponse for ClaimDataEvents {
 }
 
 // Axum Handler to query and decode the claim events
-pub async fn get_claim_events(State(state): State<Arc<AppState>>) -> Result<ClaimDataEvents, MyError> {
+pub async fn get_claim_events(SelectedNetwork(network): SelectedNetwork) -> Result<ClaimDataEvents, MyError> {
 
     let res;
 
@@ -61,8 +60,8 @@ pub async fn get_claim_events(State(state): State<Arc<AppState>>) -> Result<Clai
         // Post the GraphQL request
         let client = reqwest::Client::new();
         res = client
-                .post(format!("{}/graphql", state.mercury_graphql_endpoint))
-                .bearer_auth(state.mercury_jwt_token.lock().unwrap())
+                .post(format!("{}/graphql", network.mercury_graphql_endpoint))
+                .bearer_auth(network.mercury_jwt_token.lock().unwrap())
                 .json(&request_body)
                 .send()
                 .await?;
@@ -98,7 +97,7 @@ pub async fn get_claim_events(State(state): State<Arc<AppState>>) -> Result<Clai
             // Access the address field in the contract_id object
             let Hash(contract_id) = extract_contract_id(&decoded_val)?;
             // Retrieve the wasm hash
-            let wasm_hash = get_wasm_hash(contract_id, state.rpc_url.clone(), state.network_passphrase.clone(), state.source_account.clone()).await?;
+            let wasm_hash = get_wasm_hash(contract_id, network.rpc_url.clone(), network.network_passphrase.clone(), network.source_account.clone()).await?;
             return_data.push((decoded_val, wasm_hash));
         }
         
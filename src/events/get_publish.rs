@@ -1,8 +1,7 @@
-use crate::AppState;
+use crate::network_profile::SelectedNetwork;
 use crate::error::MyError;
-use std::{str::FromStr, sync::Arc};
+use std::str::FromStr;
 use axum::{ 
-    extract::State, 
     response::{IntoResponse, Response},
     Json 
 };
@@ -34,7 +33,7 @@ impl IntoResponse for PublishDataEvents {
 }
 
 // Axum Handler to query and decode the publish events
-pub async fn get_publish_events(State(state): State<Arc<AppState>>) -> Result<PublishDataEvents, MyError> {
+pub async fn get_publish_events(SelectedNetwork(network): SelectedNetwork) -> Result<PublishDataEvents, MyError> {
 
     let res;
 
@@ -54,8 +53,8 @@ pub async fn get_publish_events(State(state): State<Arc<AppState>>) -> Result<Pu
         // Post the GraphQL request
         let client = reqwest::Client::new();
         res = client
-                .post(format!("{}/graphql", state.mercury_graphql_endpoint))
-                .bearer_auth(state.mercury_jwt_token.lock().unwrap())
+                .post(format!("{}/graphql", network.mercury_graphql_endpoint))
+                .bearer_auth(network.mercury_jwt_token.lock().unwrap())
                 .json(&request_body)
                 .send()
                 .await?;
@@ -0,0 +1,309 @@
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
+use crate::error::MyError;
+use axum::{extract::Query, Json};
+use graphql_client::{GraphQLQuery, QueryBody, Response as GraphQLResponse};
+use serde::{Deserialize, Serialize};
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{Limits, ReadXdr, ScVal, WriteXdr};
+
+// Generate the modules query_events_by_topics and query_events_by_contract
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "queries/events/schema.graphql",
+    query_path  = "queries/events/query_filtered.graphql",
+    response_derives = "Debug, Serialize, Deserialize"
+)]
+pub struct QueryEventsByTopics;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "queries/events/schema.graphql",
+    query_path  = "queries/events/query_filtered.graphql",
+    response_derives = "Debug, Serialize, Deserialize"
+)]
+pub struct QueryEventsByContract;
+
+// Query parameters of the events handler
+// Topics are JSON ScVal (String, Symbol or Address), e.g. topic1={"string":"Deploy"}
+#[derive(Deserialize, Default)]
+pub struct EventsQuery {
+    pub topic1: Option<String>,
+    pub topic2: Option<String>,
+    pub topic3: Option<String>,
+    pub topic4: Option<String>,
+    // Contract (C...) emitting the events
+    pub contract: Option<String>,
+    // Inclusive ledger range
+    pub from_ledger: Option<i64>,
+    pub to_ledger: Option<i64>,
+}
+
+// Event node returned by Mercury, the same fragment for both queries
+struct EventNode {
+    contract_id: Option<String>,
+    topics: [Option<String>; 4],
+    data: Option<String>,
+    tx_hash: Option<String>,
+    ledger: Option<i64>,
+    close_time: Option<i64>,
+}
+
+// Each query module generates its own EventFields type
+macro_rules! event_node_from {
+    ($query:ident) => {
+        impl From<$query::EventFields> for EventNode {
+            fn from(fields: $query::EventFields) -> Self {
+                let tx_info = fields.tx_info_by_tx;
+                let ledger_info = tx_info.as_ref().and_then(|tx_info| tx_info.ledger_by_ledger.as_ref());
+                EventNode {
+                    contract_id: fields.contract_id,
+                    topics: [fields.topic1, fields.topic2, fields.topic3, fields.topic4],
+                    data: fields.data,
+                    tx_hash: tx_info.as_ref().and_then(|tx_info| tx_info.tx_hash.clone()),
+                    ledger: ledger_info.and_then(|ledger_info| ledger_info.sequence),
+                    close_time: ledger_info.and_then(|ledger_info| ledger_info.close_time),
+                }
+            }
+        }
+    };
+}
+
+event_node_from!(query_events_by_topics);
+event_node_from!(query_events_by_contract);
+
+// Handler Response: decoded event
+#[derive(Serialize, Clone)]
+pub struct DecodedEvent {
+    pub contract_id: Option<String>,
+    pub topics: Vec<ScVal>,
+    pub data: ScVal,
+    pub tx_hash: Option<String>,
+    pub ledger: Option<i64>,
+    // Unix timestamp of the ledger close
+    pub close_time: Option<i64>,
+}
+
+// Axum Handler to query and decode the events matching arbitrary topics and/or contract
+// At least topic1 or contract must be set
+pub async fn get_events(
+    SelectedNetwork(network): SelectedNetwork,
+    Query(events_query): Query<EventsQuery>,
+) -> Result<Json<Vec<DecodedEvent>>, MyError> {
+
+    let events = query_events(&network, &events_query).await?;
+
+    Ok(Json(events))
+}
+
+// Query the events indexed by Mercury, filter them and decode them
+pub async fn query_events(
+    network: &NetworkProfile,
+    events_query: &EventsQuery,
+) -> Result<Vec<DecodedEvent>, MyError> {
+
+    let topics = [
+        &events_query.topic1,
+        &events_query.topic2,
+        &events_query.topic3,
+        &events_query.topic4,
+    ]
+    .into_iter()
+    .map(|topic| topic.as_deref().map(topic_to_xdr).transpose())
+    .collect::<Result<Vec<_>, MyError>>()?;
+
+    if let Some(contract) = &events_query.contract {
+        stellar_strkey::Contract::from_string(contract)
+            .map_err(|e| MyError::StringToContractConversionFailed(contract.clone(), e))?;
+    }
+
+    // Mercury filters by topics, or by contract if no topic is set
+    // The other filters, including the ledger range, are applied to every event Mercury returns
+    let nodes: Vec<EventNode> = match (&topics[0], &events_query.contract) {
+        (Some(_), _) => {
+            let variables = query_events_by_topics::Variables {
+                t1: topics[0].clone(),
+                t2: topics[1].clone(),
+                t3: topics[2].clone(),
+                t4: topics[3].clone(),
+            };
+            post_query::<QueryEventsByTopics>(network, QueryEventsByTopics::build_query(variables))
+                .await?
+                .event_by_topic.ok_or_else(|| MyError::EmptyEventByTopic)?
+                .nodes.ok_or_else(|| MyError::EmptyNodes)?
+                .into_iter()
+                .flatten()
+                .map(EventNode::from)
+                .collect()
+        },
+        (None, Some(contract)) => {
+            let variables = query_events_by_contract::Variables {
+                contract_id: contract.clone(),
+            };
+            post_query::<QueryEventsByContract>(network, QueryEventsByContract::build_query(variables))
+                .await?
+                .event_by_contract_id.ok_or_else(|| MyError::EmptyEventByTopic)?
+                .nodes.ok_or_else(|| MyError::EmptyNodes)?
+                .into_iter()
+                .flatten()
+                .map(EventNode::from)
+                .collect()
+        },
+        (None, None) => return Err(MyError::InvalidArgument("set at least topic1 or contract".to_string())),
+    };
+
+    let mut decoded_events = Vec::new();
+
+    for node in nodes {
+
+        // Filters Mercury can't apply along with the topics
+        if events_query.contract.is_some() && node.contract_id != events_query.contract {
+            continue;
+        }
+        if topics.iter().zip(&node.topics).any(|(topic, node_topic)| topic.is_some() && topic != node_topic) {
+            continue;
+        }
+        if !in_ledger_range(node.ledger, events_query) {
+            continue;
+        }
+
+        let topics = node.topics
+            .iter()
+            .flatten()
+            .map(|topic| ScVal::from_xdr_base64(topic.as_bytes(), Limits::none()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(MyError::FromXdrError)?;
+
+        let data = match &node.data {
+            Some(data) => ScVal::from_xdr_base64(data.as_bytes(), Limits::none()).map_err(MyError::FromXdrError)?,
+            None => ScVal::Void,
+        };
+
+        decoded_events.push(DecodedEvent {
+            contract_id: node.contract_id,
+            topics,
+            data,
+            tx_hash: node.tx_hash,
+            ledger: node.ledger,
+            close_time: node.close_time,
+        });
+    }
+
+    Ok(decoded_events)
+}
+
+// Decoded events emitted by the registry with the given topic, none if Mercury has no record of them
+// or if the network has no SmartDeploy contract
+pub async fn registry_events(network: &NetworkProfile, topic: &str) -> Result<Vec<DecodedEvent>, MyError> {
+
+    let Some(registry_id) = network.smartdeploy_contract_id.clone() else {
+        return Ok(Vec::new());
+    };
+
+    let events_query = EventsQuery {
+        topic1: Some(format!("{{\"string\":\"{}\"}}", topic)),
+        contract: Some(registry_id),
+        ..Default::default()
+    };
+
+    match query_events(network, &events_query).await {
+        Ok(events) => Ok(events),
+        Err(MyError::EmptyNodes | MyError::EmptyEventByTopic) => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+async fn post_query<Q: GraphQLQuery>(
+    network: &NetworkProfile,
+    request_body: QueryBody<Q::Variables>,
+) -> Result<Q::ResponseData, MyError> {
+
+    let res;
+
+    /* Scope to drop the mutex right after the query */
+    {
+        let client = reqwest::Client::new();
+        res = client
+                .post(format!("{}/graphql", network.mercury_graphql_endpoint))
+                .bearer_auth(network.mercury_jwt_token.lock().unwrap())
+                .json(&request_body)
+                .send()
+                .await?;
+    }
+
+    if !res.status().is_success() {
+        return Err(MyError::BadResponseStatus);
+    }
+
+    tracing::debug!("GET EVENTS REQUEST SUCCEEDED");
+
+    let response_body: GraphQLResponse<Q::ResponseData> = res.json().await?;
+
+    response_body.data.ok_or_else(|| MyError::EmptyData)
+}
+
+fn in_ledger_range(ledger: Option<i64>, events_query: &EventsQuery) -> bool {
+    match ledger {
+        Some(ledger) => {
+            events_query.from_ledger.is_none_or(|from| ledger >= from)
+                && events_query.to_ledger.is_none_or(|to| ledger <= to)
+        },
+        // Can't tell, only kept without a range
+        None => events_query.from_ledger.is_none() && events_query.to_ledger.is_none(),
+    }
+}
+
+// Base64 XDR of a JSON topic, only the String, Symbol and Address ScVal are supported
+fn topic_to_xdr(topic: &str) -> Result<String, MyError> {
+
+    let scval = serde_json::from_str::<ScVal>(topic)
+        .map_err(|e| MyError::InvalidArgument(format!("topic {} is not a JSON ScVal: {}", topic, e)))?;
+
+    match scval {
+        ScVal::String(_) | ScVal::Symbol(_) | ScVal::Address(_) => {
+            scval.to_xdr_base64(Limits::none()).map_err(MyError::ToXdrError)
+        },
+        _ => Err(MyError::InvalidArgument(format!("topic {} is not a String, Symbol or Address", topic))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::stellar_xdr::curr::{ScString, StringM};
+
+    fn range(from_ledger: Option<i64>, to_ledger: Option<i64>) -> EventsQuery {
+        EventsQuery { from_ledger, to_ledger, ..Default::default() }
+    }
+
+    #[test]
+    fn ledger_range_is_inclusive() {
+        let events_query = range(Some(10), Some(20));
+
+        assert!(in_ledger_range(Some(10), &events_query));
+        assert!(in_ledger_range(Some(20), &events_query));
+        assert!(!in_ledger_range(Some(9), &events_query));
+        assert!(!in_ledger_range(Some(21), &events_query));
+        assert!(in_ledger_range(Some(5), &range(None, Some(20))));
+    }
+
+    #[test]
+    fn events_without_ledger_are_only_kept_without_range() {
+        assert!(in_ledger_range(None, &range(None, None)));
+        assert!(!in_ledger_range(None, &range(Some(10), None)));
+        assert!(!in_ledger_range(None, &range(None, Some(20))));
+    }
+
+    #[test]
+    fn topics_are_encoded_as_scval_xdr() {
+        let xdr = topic_to_xdr("{\"string\":\"Deploy\"}").ok().unwrap();
+
+        let expected = ScVal::String(ScString(StringM::try_from("Deploy").unwrap()));
+        assert_eq!(ScVal::from_xdr_base64(xdr, Limits::none()).unwrap(), expected);
+    }
+
+    #[test]
+    fn unsupported_topics_are_rejected() {
+        assert!(topic_to_xdr("{\"u32\":1}").is_err());
+        assert!(topic_to_xdr("Deploy").is_err());
+    }
+}
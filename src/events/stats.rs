@@ -0,0 +1,210 @@
+use crate::AppState;
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
+use crate::error::MyError;
+use crate::events::event_data::{event_address, event_string, RegistryEvent};
+use crate::events::query_events::{registry_events, DecodedEvent};
+use crate::expiration::footprint::{EXTEND_KIND, RESTORE_KIND};
+use crate::transactions::relay::RELAY_BUMP_KIND;
+use crate::transactions::submit::STATUS_SUCCESS;
+use axum::{extract::State, Json};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The statistics are computed again after 10 minutes
+const STATS_CACHE_SECS: u64 = 10 * 60;
+// Length of the rankings
+const TOP_LENGTH: usize = 10;
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+// Handler Response: statistics of the registry on the selected network
+#[derive(Serialize, Clone)]
+pub struct RegistryStats {
+    pub published_contracts: usize,
+    pub published_versions: usize,
+    pub deployments: usize,
+    pub claims: usize,
+    // Unix timestamp of the day (00:00 UTC) and number of events that day
+    pub daily_publishes: Vec<DailyCount>,
+    pub daily_deploys: Vec<DailyCount>,
+    pub most_deployed: Vec<Ranking>,
+    pub most_active_authors: Vec<Ranking>,
+    #[serde(flatten)]
+    pub ttl: TtlStats,
+    // Unix timestamp
+    pub computed_at: u64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct DailyCount {
+    pub day: i64,
+    pub count: usize,
+}
+
+// Published contract name or author address with its number of deploys or publishes
+#[derive(Serialize, Clone)]
+pub struct Ranking {
+    pub name: String,
+    pub count: usize,
+}
+
+// Contracts tracked in contracts_ttl and fees (in stroops) spent bumping them
+#[derive(Serialize, Clone, FromRow)]
+pub struct TtlStats {
+    pub tracked_contracts: i64,
+    pub auto_bumped_contracts: i64,
+    pub bump_transactions: i64,
+    pub bump_fees_charged: i64,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+// Axum Handler to get the statistics of the registry, cached for 10 minutes
+pub async fn get_stats(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+) -> Result<Json<RegistryStats>, MyError> {
+
+    if let Some(stats) = network.stats.lock().unwrap().as_ref() {
+        if stats.computed_at + STATS_CACHE_SECS > now() {
+            return Ok(Json(stats.clone()));
+        }
+    }
+
+    let stats = compute_stats(&state.ttl_pool, &network).await?;
+    *network.stats.lock().unwrap() = Some(stats.clone());
+
+    Ok(Json(stats))
+}
+
+// Compute the statistics from the decoded registry events and contracts_ttl
+pub async fn compute_stats(pool: &PgPool, network: &NetworkProfile) -> Result<RegistryStats, MyError> {
+
+    let publish_events = registry_events(network, RegistryEvent::Publish.topic()).await?;
+    let deploy_events = registry_events(network, RegistryEvent::Deploy.topic()).await?;
+    let claim_events = registry_events(network, RegistryEvent::Claim.topic()).await?;
+
+    let published_contracts = publish_events
+        .iter()
+        .filter_map(|event| event_string(&event.data, RegistryEvent::Publish.name_field()))
+        .collect::<HashSet<_>>()
+        .len();
+
+    let most_deployed = ranking(deploy_events.iter().filter_map(|event| event_string(&event.data, "published_name")));
+    let most_active_authors = ranking(publish_events.iter().filter_map(|event| event_address(&event.data, RegistryEvent::Publish.actor_field())));
+
+    let ttl = sqlx::query_as::<_, TtlStats>("SELECT
+                                        (SELECT COUNT(*) FROM contracts_ttl WHERE network = $1) AS tracked_contracts,
+                                        (SELECT COUNT(*) FROM contracts_ttl WHERE network = $1 AND automatic_bump) AS auto_bumped_contracts,
+                                        COUNT(*) AS bump_transactions,
+                                        COALESCE(SUM(fee_charged), 0)::BIGINT AS bump_fees_charged
+                                        FROM transactions
+                                        WHERE network = $1 AND status = $2 AND kind IN ($3, $4, $5)
+                                    ")
+                                    .bind(&network.name)
+                                    .bind(STATUS_SUCCESS)
+                                    .bind(EXTEND_KIND)
+                                    .bind(RELAY_BUMP_KIND)
+                                    .bind(RESTORE_KIND)
+                                    .fetch_one(pool)
+                                    .await?;
+
+    Ok(RegistryStats {
+        published_contracts,
+        published_versions: publish_events.len(),
+        deployments: deploy_events.len(),
+        claims: claim_events.len(),
+        daily_publishes: daily_counts(&publish_events),
+        daily_deploys: daily_counts(&deploy_events),
+        most_deployed,
+        most_active_authors,
+        ttl,
+        computed_at: now(),
+    })
+}
+
+fn daily_counts(events: &[DecodedEvent]) -> Vec<DailyCount> {
+
+    let mut counts = BTreeMap::<i64, usize>::new();
+    for close_time in events.iter().filter_map(|event| event.close_time) {
+        *counts.entry(close_time - close_time.rem_euclid(SECS_PER_DAY)).or_default() += 1;
+    }
+
+    counts
+        .into_iter()
+        .map(|(day, count)| DailyCount { day, count })
+        .collect()
+}
+
+// Most frequent names first
+fn ranking(names: impl Iterator<Item = String>) -> Vec<Ranking> {
+
+    let mut counts = HashMap::<String, usize>::new();
+    for name in names {
+        *counts.entry(name).or_default() += 1;
+    }
+
+    let mut ranking = counts
+        .into_iter()
+        .map(|(name, count)| Ranking { name, count })
+        .collect::<Vec<_>>();
+    ranking.sort_by(|a, b| b.count.cmp(&a.count).then(a.name.cmp(&b.name)));
+    ranking.truncate(TOP_LENGTH);
+
+    ranking
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::stellar_xdr::curr::ScVal;
+
+    fn event(close_time: Option<i64>) -> DecodedEvent {
+        DecodedEvent {
+            contract_id: None,
+            topics: Vec::new(),
+            data: ScVal::Void,
+            tx_hash: None,
+            ledger: None,
+            close_time,
+        }
+    }
+
+    #[test]
+    fn events_are_counted_by_utc_day() {
+        let events = [
+            event(Some(2 * SECS_PER_DAY + 10)),
+            event(Some(SECS_PER_DAY + 5)),
+            event(Some(2 * SECS_PER_DAY - 1)),
+            event(None),
+        ];
+
+        let counts = daily_counts(&events)
+            .into_iter()
+            .map(|daily_count| (daily_count.day, daily_count.count))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![(SECS_PER_DAY, 2), (2 * SECS_PER_DAY, 1)]);
+    }
+
+    #[test]
+    fn most_frequent_names_come_first() {
+        let names = ["b", "a", "c", "b", "a", "b"].map(String::from);
+
+        let ranking = ranking(names.into_iter())
+            .into_iter()
+            .map(|ranking| (ranking.name, ranking.count))
+            .collect::<Vec<_>>();
+        assert_eq!(ranking, vec![("b".to_string(), 3), ("a".to_string(), 2), ("c".to_string(), 1)]);
+    }
+
+    #[test]
+    fn rankings_are_truncated() {
+        let names = (0..TOP_LENGTH + 5).map(|i| format!("name{:02}", i));
+
+        assert_eq!(ranking(names).len(), TOP_LENGTH);
+    }
+}
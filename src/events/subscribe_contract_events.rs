@@ -0,0 +1,95 @@
+use crate::AppState;
+use crate::admin::AdminAuth;
+use crate::network_profile::SelectedNetwork;
+use crate::error::MyError;
+use crate::expiration::read_ledger::read_contract_ttl;
+use crate::postgres::subscriptions::{record_subscription, Subscription, EVENT_SUBSCRIPTION};
+use axum::{
+    extract::{Path, Query, State},
+    Json
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{Limits, ReadXdr, ScVal};
+
+// Largest event data (in bytes) indexed by Mercury
+const MAX_SINGLE_SIZE: u32 = 200;
+
+// Optional topics (base64 XDR ScVal) the events must match
+#[derive(Deserialize)]
+pub struct TopicsQuery {
+    pub topic1: Option<String>,
+    pub topic2: Option<String>,
+    pub topic3: Option<String>,
+    pub topic4: Option<String>,
+}
+
+// Axum Handler for subscribing to the events of a deployed contract through Mercury
+// Admin only: every subscription is indexed (and paid for) on the Mercury account of the API
+pub async fn subscribe_contract_events(
+    _admin: AdminAuth,
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Path(id): Path<String>,
+    Query(topics_query): Query<TopicsQuery>,
+) -> Result<Json<Subscription>, MyError> {
+
+    // Fails if the contract isn't deployed
+    read_contract_ttl(
+        id.clone(),
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    ).await?;
+
+    // Create the request body
+    let mut request_body = json!({
+        "contract_id": id,
+        "max_single_size": MAX_SINGLE_SIZE,
+    });
+
+    let topics = [
+        ("topic1", topics_query.topic1),
+        ("topic2", topics_query.topic2),
+        ("topic3", topics_query.topic3),
+        ("topic4", topics_query.topic4),
+    ];
+    let mut subscribed_topics = Vec::new();
+    for (name, topic) in topics {
+        if let Some(topic) = topic {
+            ScVal::from_xdr_base64(&topic, Limits::none()).map_err(MyError::FromXdrError)?;
+            request_body[name] = json!(topic);
+            subscribed_topics.push(topic);
+        }
+    }
+
+    let client = reqwest::Client::new();
+    let res = client.post(format!("{}/event", network.mercury_backend_endpoint))
+        .bearer_auth(network.mercury_jwt_token.lock().unwrap())
+        .json(&request_body)
+        .send()
+        .await?;
+    tracing::debug!("Mercury subscription response: {:#?}", res);
+
+    if res.status().is_success() {
+
+        let mercury_response = res.text().await?;
+        let subscription = record_subscription(
+            &state.ttl_pool,
+            &network,
+            EVENT_SUBSCRIPTION,
+            &id,
+            None,
+            &subscribed_topics,
+            &mercury_response
+        ).await?;
+
+        tracing::debug!("SUCCESSFULLY SUBSCRIBE TO CONTRACT EVENTS: {}", id);
+
+        return Ok(Json(subscription));
+    }
+
+    Err(MyError::BadResponseStatus)
+}
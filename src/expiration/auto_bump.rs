@@ -0,0 +1,138 @@
+use crate::AppState;
+use crate::network_profile::NetworkProfile;
+use crate::error::MyError;
+use tokio::time::{Duration, sleep};
+use std::sync::Arc;
+use super::{
+    read_ledger::read_contract_ttl,
+    extend_ttl::extend_contract,
+    restore::restore_contract,
+    batch_extend::{extend_contracts_batch, BatchBumpStatus},
+};
+
+// Remaining ttl (in ledgers) under which a contract is bumped: ~7 days
+const AUTO_BUMP_THRESHOLD: u32 = 17_280 * 7;
+// Number of ledgers a contract is extended by: ~30 days
+const AUTO_BUMP_LEDGERS_TO_EXTEND: u32 = 17_280 * 30;
+
+// Function to automatically bump (or restore) the contracts of a network with automatic_bump enabled, every hour
+// In batch mode (AUTO_BUMP_BATCH = 'true'), contracts to bump are extended together in as few transactions as possible
+pub async fn auto_bump_cron_job(state: Arc<AppState>, network: Arc<NetworkProfile>) {
+
+    tokio::spawn(async move {
+
+        loop {
+
+            tracing::debug!("Running contracts automatic bump on network {}", network.name);
+
+            match sqlx::query_scalar::<_, String>("SELECT contract_id FROM contracts_ttl WHERE network = $1 AND automatic_bump = TRUE")
+                .bind(&network.name)
+                .fetch_all(&state.ttl_pool)
+                .await {
+
+                    Ok(contract_ids) => {
+                        let mut contracts_to_bump = Vec::new();
+                        for contract_id in contract_ids {
+                            match auto_bump_contract(&state, &network, contract_id.clone()).await {
+                                Ok(AutoBumpAction::Bump) => contracts_to_bump.push(contract_id),
+                                Ok(AutoBumpAction::Done) => (),
+                                Err(e) => tracing::error!("Error while automatically bumping {}: {}", contract_id, e),
+                            }
+                        }
+                        if !contracts_to_bump.is_empty() {
+                            auto_bump_batch(&state, &network, contracts_to_bump).await;
+                        }
+                    },
+
+                    Err(e) => {
+                        tracing::error!("Error while retrieving contracts to bump: {:?}", e);
+                    }
+            };
+
+            // Wait an hour before checking the contracts again
+            sleep(Duration::from_secs(60 * 60)).await;
+        }
+    });
+}
+
+// What is left to do for a contract once auto_bump_contract returns
+enum AutoBumpAction {
+    // Nothing, the contract is up to date
+    Done,
+    // The contract must be bumped with the other contracts of the batch
+    Bump,
+}
+
+// Restore the contract if its ttl was missed, bump it if it's close to expiration
+async fn auto_bump_contract(state: &AppState, network: &NetworkProfile, contract_id: String) -> Result<AutoBumpAction, MyError> {
+
+    let contract_ttl = read_contract_ttl(
+        contract_id.clone(),
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    )
+    .await?;
+
+    let live_until_ledger = if contract_ttl.is_archived() {
+        tracing::debug!("Contract {} is archived, restoring it", contract_id);
+        let restored_ttl = restore_contract(&state.ttl_pool, network, contract_id.clone(), Some(AUTO_BUMP_LEDGERS_TO_EXTEND)).await?;
+        restored_ttl.instance_live_until_ledger
+    } else if !contract_ttl.lives_at_least(AUTO_BUMP_THRESHOLD) {
+        if state.auto_bump_batch {
+            return Ok(AutoBumpAction::Bump);
+        }
+        tracing::debug!("Contract {} is close to expiration, bumping it", contract_id);
+        let receipt = extend_contract(&state.ttl_pool, network, contract_id.clone(), AUTO_BUMP_LEDGERS_TO_EXTEND).await?;
+        receipt.live_until_ledger
+    } else {
+        return Ok(AutoBumpAction::Done);
+    };
+
+    update_live_until_ttl(state, network, contract_id, live_until_ledger).await?;
+
+    Ok(AutoBumpAction::Done)
+}
+
+// Bump all the contracts close to expiration together
+async fn auto_bump_batch(state: &AppState, network: &NetworkProfile, contract_ids: Vec<String>) {
+
+    tracing::debug!("Batch bumping {} contracts close to expiration", contract_ids.len());
+
+    let results = match extend_contracts_batch(&state.ttl_pool, network, contract_ids, AUTO_BUMP_LEDGERS_TO_EXTEND).await {
+        Ok(results) => results,
+        Err(e) => {
+            tracing::error!("Error while automatically batch bumping: {}", e);
+            return;
+        }
+    };
+
+    for result in results {
+        match (result.status, result.instance_live_until_ledger) {
+            (BatchBumpStatus::Bumped, Some(live_until_ledger)) => {
+                if let Err(e) = update_live_until_ttl(state, network, result.contract_id.clone(), live_until_ledger).await {
+                    tracing::error!("Error while updating ttl of {}: {}", result.contract_id, e);
+                }
+            },
+            _ => {
+                tracing::error!(
+                    "Error while automatically bumping {}: {}",
+                    result.contract_id,
+                    result.error.unwrap_or_default()
+                );
+            }
+        }
+    }
+}
+
+async fn update_live_until_ttl(state: &AppState, network: &NetworkProfile, contract_id: String, live_until_ledger: u32) -> Result<(), MyError> {
+
+    sqlx::query("UPDATE contracts_ttl SET live_until_ttl = $1 WHERE network = $2 AND contract_id = $3")
+        .bind(live_until_ledger as i32)
+        .bind(&network.name)
+        .bind(contract_id)
+        .execute(&state.ttl_pool)
+        .await?;
+
+    Ok(())
+}
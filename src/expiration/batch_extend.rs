@@ -0,0 +1,261 @@
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
+use crate::error::MyError;
+use crate::balance_monitor::check_manual_bump_funds;
+use crate::AppState;
+use axum::extract::State;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use stellar_xdr::curr::LedgerKey;
+use super::read_ledger::{read_contract_ttl, ContractTtl};
+use super::footprint::{send_footprint_transaction, FootprintOp};
+use crate::transactions::submit::TrackedTransaction;
+
+// Ledger entries per transaction footprint, kept under the network read entries limit (40)
+// Batches whose entries exceed the other limits (e.g. read bytes, with large contract codes) are split
+const MAX_KEYS_PER_TRANSACTION: usize = 30;
+// Contracts bumped by a single request
+const MAX_CONTRACTS_PER_REQUEST: usize = 100;
+
+// Handler Request
+#[derive(Deserialize)]
+pub struct BatchBumpRequest {
+    pub contract_ids: Vec<String>,
+    pub ledgers_to_extend: u32,
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BatchBumpStatus {
+    Bumped,
+    Archived,
+    Failed,
+}
+
+// Result of the batch bump for a single contract
+#[derive(Serialize)]
+pub struct BatchBumpResult {
+    pub contract_id: String,
+    pub status: BatchBumpStatus,
+    pub instance_live_until_ledger: Option<u32>,
+    pub instance_ttl: Option<u32>,
+    pub code_ttl: Option<u32>,
+    // Transaction extending the contract, shared by the contracts of the same batch
+    // None if the contract already lived long enough
+    pub tx_hash: Option<String>,
+    // Fee (in stroops) charged for the whole transaction
+    pub fee_charged: Option<i64>,
+    pub error: Option<String>,
+}
+
+impl BatchBumpResult {
+    fn failed(contract_id: String, status: BatchBumpStatus, tx_hash: Option<String>, error: String) -> Self {
+        BatchBumpResult {
+            contract_id,
+            status,
+            instance_live_until_ledger: None,
+            instance_ttl: None,
+            code_ttl: None,
+            tx_hash,
+            fee_charged: None,
+            error: Some(error),
+        }
+    }
+}
+
+// Handler to bump many contract instances (and their code) in as few transactions as possible
+pub async fn bump_contract_instances(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Json(batch_request): Json<BatchBumpRequest>,
+) -> Result<Json<Vec<BatchBumpResult>>, MyError> {
+
+    if batch_request.contract_ids.len() > MAX_CONTRACTS_PER_REQUEST {
+        return Err(MyError::InvalidArgument(format!("at most {} contracts can be bumped at once", MAX_CONTRACTS_PER_REQUEST)));
+    }
+
+    check_manual_bump_funds(&network)?;
+
+    let results = extend_contracts_batch(
+        &state.ttl_pool,
+        &network,
+        batch_request.contract_ids,
+        batch_request.ledgers_to_extend
+    )
+    .await?;
+
+    Ok(Json(results))
+}
+
+// Extend the instance and code entries of every contract, grouping the ledger keys into
+// extend footprint ttl transactions. Return a result per contract.
+pub async fn extend_contracts_batch(
+    pool: &PgPool,
+    network: &NetworkProfile,
+    contract_ids: Vec<String>,
+    ledgers_to_extend: u32,
+) -> Result<Vec<BatchBumpResult>, MyError> {
+
+    // Wait for the bumps of these contracts already in flight
+    // (locked in a fixed order so that two batches can't wait for each other)
+    let mut locked_ids = contract_ids.clone();
+    locked_ids.sort();
+    locked_ids.dedup();
+    let mut _contract_locks = Vec::new();
+    for contract_id in &locked_ids {
+        _contract_locks.push(network.contract_locks.lock(contract_id).await);
+    }
+
+    let mut results = Vec::new();
+
+    // Contracts to extend with their ledger keys, grouped by transaction
+    let mut batches: VecDeque<Vec<(String, Vec<LedgerKey>)>> = VecDeque::new();
+
+    for contract_id in contract_ids {
+
+        let keys = match contract_ledger_keys(network, contract_id.clone()).await {
+            // Already bumped, e.g. by the request we waited for
+            Ok((_, contract_ttl)) if contract_ttl.lives_at_least(ledgers_to_extend) => {
+                results.push(bumped_result(contract_id, contract_ttl, None));
+                continue;
+            },
+            Ok((keys, _)) => keys,
+            Err(e) => {
+                let status = match e {
+                    MyError::ContractArchived(_) => BatchBumpStatus::Archived,
+                    _ => BatchBumpStatus::Failed,
+                };
+                results.push(BatchBumpResult::failed(contract_id, status, None, e.to_string()));
+                continue;
+            }
+        };
+
+        let fits_in_last_batch = batches.back().is_some_and(|batch| {
+            let batch_keys = batch_keys(batch);
+            let new_keys = keys.iter().filter(|key| !batch_keys.contains(key)).count();
+            batch_keys.len() + new_keys <= MAX_KEYS_PER_TRANSACTION
+        });
+        if !fits_in_last_batch {
+            batches.push_back(Vec::new());
+        }
+        batches.back_mut().unwrap().push((contract_id, keys));
+    }
+
+    while let Some(batch) = batches.pop_front() {
+
+        let keys = batch_keys(&batch);
+        tracing::debug!("Extending {} ledger entries of {} contracts", keys.len(), batch.len());
+
+        let transaction = match send_footprint_transaction(pool, network, keys, FootprintOp::Extend(ledgers_to_extend)).await {
+            Ok(transaction) => transaction,
+            // The batch may exceed the resource limits of a transaction: try again with each half
+            Err(MyError::RpcError(soroban_cli::rpc::Error::TransactionSimulationFailed(e))) if batch.len() > 1 => {
+                tracing::debug!("Splitting a batch of {} contracts failing simulation: {}", batch.len(), e);
+                let mut first_half = batch;
+                let second_half = first_half.split_off(first_half.len() / 2);
+                batches.push_front(second_half);
+                batches.push_front(first_half);
+                continue;
+            },
+            // A failed transaction fails all the contracts of the batch
+            Err(e) => {
+                let tx_hash = match &e {
+                    MyError::TransactionFailed(hash, _) => Some(hash.clone()),
+                    _ => None,
+                };
+                let error = e.to_string();
+                for (contract_id, _) in batch {
+                    results.push(BatchBumpResult::failed(contract_id, BatchBumpStatus::Failed, tx_hash.clone(), error.clone()));
+                }
+                continue;
+            }
+        };
+
+        // Read ledger ttls to return the new ttls
+        for (contract_id, _) in batch {
+            let result = match read_contract_ttl(
+                contract_id.clone(),
+                network.rpc_url.clone(),
+                network.network_passphrase.clone(),
+                network.source_account.clone()
+            ).await {
+                Ok(contract_ttl) => bumped_result(contract_id, contract_ttl, Some(&transaction)),
+                Err(e) => BatchBumpResult::failed(contract_id, BatchBumpStatus::Failed, Some(transaction.hash.clone()), e.to_string()),
+            };
+            results.push(result);
+        }
+    }
+
+    Ok(results)
+}
+
+// Ledger keys of the contracts of a batch, several contracts may share the same code entry
+fn batch_keys(batch: &[(String, Vec<LedgerKey>)]) -> Vec<LedgerKey> {
+
+    let mut keys: Vec<LedgerKey> = Vec::new();
+    for key in batch.iter().flat_map(|(_, contract_keys)| contract_keys) {
+        if !keys.contains(key) {
+            keys.push(key.clone());
+        }
+    }
+
+    keys
+}
+
+// Ledger keys of the contract instance and of its code, with its current ttl
+async fn contract_ledger_keys(network: &NetworkProfile, contract_id: String) -> Result<(Vec<LedgerKey>, ContractTtl), MyError> {
+
+    let contract_ttl = read_contract_ttl(
+        contract_id.clone(),
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    )
+    .await?;
+
+    // Archived entries can't be extended, they must be restored first
+    if contract_ttl.is_archived() {
+        return Err(MyError::ContractArchived(contract_id));
+    }
+
+    let keys = contract_ttl.ledger_keys(&contract_id)?;
+
+    Ok((keys, contract_ttl))
+}
+
+fn bumped_result(contract_id: String, contract_ttl: ContractTtl, transaction: Option<&TrackedTransaction>) -> BatchBumpResult {
+
+    BatchBumpResult {
+        contract_id,
+        status: BatchBumpStatus::Bumped,
+        instance_live_until_ledger: Some(contract_ttl.instance_live_until_ledger),
+        instance_ttl: Some(contract_ttl.instance_ttl()),
+        code_ttl: contract_ttl.code_ttl(),
+        tx_hash: transaction.map(|transaction| transaction.hash.clone()),
+        fee_charged: transaction.and_then(|transaction| transaction.fee_charged),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expiration::ledger_key::instance_ledger_key;
+
+    fn instance_key(byte: u8) -> LedgerKey {
+        instance_ledger_key(&stellar_strkey::Contract([byte; 32]).to_string()).ok().unwrap()
+    }
+
+    #[test]
+    fn batch_keys_are_shared_by_the_contracts() {
+        let code_key = instance_key(0);
+        let batch = vec![
+            ("C1".to_string(), vec![instance_key(1), code_key.clone()]),
+            ("C2".to_string(), vec![instance_key(2), code_key.clone()]),
+        ];
+
+        assert_eq!(batch_keys(&batch), vec![instance_key(1), code_key, instance_key(2)]);
+    }
+}
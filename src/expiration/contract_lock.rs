@@ -0,0 +1,163 @@
+use super::extend_ttl::BumpReceipt;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+type LockMap = Mutex<HashMap<String, Arc<AsyncMutex<Option<LastBump>>>>>;
+
+// Per-contract locks serializing the bumps and restores of a contract, so that overlapping
+// requests (double clicks, auto-bump worker) don't pay for the same transaction twice
+// Each lock holds the receipt of the last bump, shared with the requests that waited for it
+#[derive(Default)]
+pub struct ContractLocks {
+    locks: Arc<LockMap>,
+}
+
+// What a bump extended
+#[derive(Clone, PartialEq, Debug)]
+pub enum BumpTarget {
+    // Instance and code of the contract
+    Contract,
+    // Single ledger entry of the contract, by its base64 key XDR
+    LedgerEntry(String),
+}
+
+pub struct LastBump {
+    target: BumpTarget,
+    ledgers_to_extend: u32,
+    receipt: BumpReceipt,
+}
+
+// Held while bumping the contract, the lock is forgotten once nobody holds or waits for it
+pub struct ContractLockGuard {
+    guard: Option<OwnedMutexGuard<Option<LastBump>>>,
+    contract_id: String,
+    locks: Arc<LockMap>,
+}
+
+impl ContractLocks {
+    // Wait until no other bump of the contract is in flight
+    pub async fn lock(&self, contract_id: &str) -> ContractLockGuard {
+
+        let lock = self.locks
+            .lock()
+            .unwrap()
+            .entry(contract_id.to_string())
+            .or_default()
+            .clone();
+
+        ContractLockGuard {
+            guard: Some(lock.lock_owned().await),
+            contract_id: contract_id.to_string(),
+            locks: self.locks.clone(),
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.locks.lock().unwrap().len()
+    }
+}
+
+impl ContractLockGuard {
+    // Receipt of the last bump if it extended the same target by at least as many ledgers
+    pub fn receipt_for(&self, target: &BumpTarget, ledgers_to_extend: u32) -> Option<BumpReceipt> {
+        self.as_ref()
+            .filter(|last_bump| last_bump.target == *target && last_bump.ledgers_to_extend >= ledgers_to_extend)
+            .map(|last_bump| last_bump.receipt.clone())
+    }
+
+    // Share the receipt with the requests waiting for the lock
+    pub fn record(&mut self, target: BumpTarget, ledgers_to_extend: u32, receipt: BumpReceipt) {
+        **self = Some(LastBump { target, ledgers_to_extend, receipt });
+    }
+}
+
+impl Deref for ContractLockGuard {
+    type Target = Option<LastBump>;
+
+    fn deref(&self) -> &Self::Target {
+        self.guard.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for ContractLockGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.guard.as_mut().unwrap()
+    }
+}
+
+impl Drop for ContractLockGuard {
+    fn drop(&mut self) {
+
+        // Unlock first, so that only the map refers to a lock nobody waits for
+        self.guard.take();
+
+        let mut locks = self.locks.lock().unwrap();
+        if locks.get(&self.contract_id).is_some_and(|lock| Arc::strong_count(lock) == 1) {
+            locks.remove(&self.contract_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(transaction_hash: &str) -> BumpReceipt {
+        BumpReceipt {
+            transaction_hash: Some(transaction_hash.to_string()),
+            ledger: None,
+            fee_charged: None,
+            old_ttl: 0,
+            new_ttl: 100,
+            live_until_ledger: 100,
+            code_old_ttl: None,
+            code_new_ttl: None,
+            code_live_until_ledger: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn locks_are_removed_when_unlocked() {
+        let locks = ContractLocks::default();
+
+        let guard = locks.lock("C1").await;
+        assert_eq!(locks.len(), 1);
+
+        drop(guard);
+        assert_eq!(locks.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn receipts_are_shared_with_the_waiting_requests() {
+        let locks = Arc::new(ContractLocks::default());
+
+        let mut guard = locks.lock("C1").await;
+        let waiting = tokio::spawn({
+            let locks = locks.clone();
+            async move { locks.lock("C1").await.receipt_for(&BumpTarget::Contract, 100) }
+        });
+        tokio::task::yield_now().await;
+
+        guard.record(BumpTarget::Contract, 100, receipt("hash"));
+        drop(guard);
+
+        let shared = waiting.await.unwrap();
+        assert_eq!(shared.and_then(|receipt| receipt.transaction_hash), Some("hash".to_string()));
+        assert_eq!(locks.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn receipts_are_only_shared_for_the_same_bump() {
+        let locks = ContractLocks::default();
+
+        let mut guard = locks.lock("C1").await;
+        guard.record(BumpTarget::LedgerEntry("key".to_string()), 100, receipt("hash"));
+
+        assert!(guard.receipt_for(&BumpTarget::Contract, 100).is_none());
+        assert!(guard.receipt_for(&BumpTarget::LedgerEntry("key".to_string()), 200).is_none());
+        assert!(guard.receipt_for(&BumpTarget::LedgerEntry("key".to_string()), 50).is_some());
+    }
+}
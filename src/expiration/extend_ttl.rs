@@ -1,59 +1,212 @@
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
 use crate::AppState;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use serde::Serialize;
+use sqlx::PgPool;
 use std::sync::Arc;
 use crate::error::MyError;
-use soroban_cli::commands::{
-    contract::Durability,
-    network,
-    {contract::extend, config},
-};
-use soroban_cli::{fee, key};
-use super::read_ledger::read_ledger_ttl;
-
-// Handler to bump a contract instance
+use crate::balance_monitor::check_manual_bump_funds;
+use super::read_ledger::{read_contract_ttl, read_ledger_entry_ttl};
+use super::ledger_key::LedgerKeyQuery;
+use super::footprint::{send_footprint_transaction, FootprintOp, MAX_LEDGERS_TO_EXTEND};
+use super::contract_lock::BumpTarget;
+use stellar_xdr::curr::{Limits, WriteXdr};
+
+// Handler Response: receipt of the bump transaction
+// No transaction is sent (transaction_hash not set) when the ttl is already high enough
+#[derive(Serialize, Clone)]
+pub struct BumpReceipt {
+    pub transaction_hash: Option<String>,
+    pub ledger: Option<i32>,
+    // Fee (in stroops) charged by the network
+    pub fee_charged: Option<i64>,
+    // Ttl of the contract instance (or of the ledger entry) before and after the bump
+    pub old_ttl: u32,
+    pub new_ttl: u32,
+    pub live_until_ledger: u32,
+    // Same for the contract code, not set when bumping a single ledger entry
+    pub code_old_ttl: Option<u32>,
+    pub code_new_ttl: Option<u32>,
+    pub code_live_until_ledger: Option<u32>,
+}
+
+// Handler to bump a contract instance and the contract code (wasm) it runs
 // Need the contract id and the number of ledgers to extend
 pub async fn bump_contract_instance(
     State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
     Path((id, ledgers_to_extend)): Path<(String, u32)>
-) -> Result<String, MyError> {
+) -> Result<Json<BumpReceipt>, MyError> {
 
-    let network = network::Args {
-        rpc_url: Some(state.rpc_url.clone()),
-        network_passphrase: Some(state.network_passphrase.clone()),
-        network: None,
+    check_manual_bump_funds(&network)?;
+
+    let receipt = extend_contract(&state.ttl_pool, &network, id, ledgers_to_extend).await?;
+
+    Ok(Json(receipt))
+}
+
+// Extend the contract instance and the contract code (wasm) it runs
+// Concurrent bumps of the same contract wait for the first one and share its receipt
+pub async fn extend_contract(
+    pool: &PgPool,
+    network: &NetworkProfile,
+    id: String,
+    ledgers_to_extend: u32,
+) -> Result<BumpReceipt, MyError> {
+
+    let mut contract_lock = network.contract_locks.lock(&id).await;
+
+    // Archived entries can't be extended, they must be restored first
+    let contract_ttl = read_contract_ttl(
+        id.clone(),
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    )
+    .await?;
+
+    if contract_ttl.is_archived() {
+        return Err(MyError::ContractArchived(id));
+    }
+
+    // Already bumped, e.g. by the request we waited for
+    if contract_ttl.lives_at_least(ledgers_to_extend) {
+        if let Some(receipt) = contract_lock.receipt_for(&BumpTarget::Contract, ledgers_to_extend) {
+            return Ok(receipt);
+        }
+        let ttl = contract_ttl.instance_ttl();
+        let code_ttl = contract_ttl.code_ttl();
+        return Ok(BumpReceipt {
+            transaction_hash: None,
+            ledger: None,
+            fee_charged: None,
+            old_ttl: ttl,
+            new_ttl: ttl,
+            live_until_ledger: contract_ttl.instance_live_until_ledger,
+            code_old_ttl: code_ttl,
+            code_new_ttl: code_ttl,
+            code_live_until_ledger: contract_ttl.code_live_until_ledger,
+        });
+    }
+
+    // Extend the contract instance and the contract code in a single transaction
+    let keys = contract_ttl.ledger_keys(&id)?;
+
+    let transaction = send_footprint_transaction(pool, network, keys, FootprintOp::Extend(ledgers_to_extend)).await?;
+
+    // Read ledger ttl to return the new ttls
+    let new_contract_ttl = read_contract_ttl(
+        id,
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    )
+    .await?;
+
+    let receipt = BumpReceipt {
+        transaction_hash: Some(transaction.hash),
+        ledger: transaction.ledger,
+        fee_charged: transaction.fee_charged,
+        old_ttl: contract_ttl.instance_ttl(),
+        new_ttl: new_contract_ttl.instance_ttl(),
+        live_until_ledger: new_contract_ttl.instance_live_until_ledger,
+        code_old_ttl: contract_ttl.code_ttl(),
+        code_new_ttl: new_contract_ttl.code_ttl(),
+        code_live_until_ledger: new_contract_ttl.code_live_until_ledger,
     };
 
-    extend::Cmd {
-        ledgers_to_extend,
-        key: key::Args {
-            contract_id: Some(id.clone()),
-            key: None,
-            key_xdr: None,
-            wasm: None,
-            wasm_hash: None,
-            durability: Durability::Persistent,
-        },   
-        ttl_ledger_only: false,
-        config: config::Args {
-            network,
-            source_account: state.source_account.clone(),
-            ..Default::default()
-        },
-        fee: fee::Args::default(),
+    contract_lock.record(BumpTarget::Contract, ledgers_to_extend, receipt.clone());
+
+    Ok(receipt)
+}
+
+// Handler to bump any contract data entry (instance, persistent or temporary storage)
+// Need the contract id, the number of ledgers to extend and the entry key as query parameters
+pub async fn bump_ledger_entry(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Path((id, ledgers_to_extend)): Path<(String, u32)>,
+    Query(key_query): Query<LedgerKeyQuery>,
+) -> Result<Json<BumpReceipt>, MyError> {
+
+    check_manual_bump_funds(&network)?;
+
+    let key = key_query.key_args(id.clone())?;
+    let ledger_keys = key.parse_keys()?;
+
+    // Bumps of the contract (and of its entries) wait for each other,
+    // the concurrent bumps of the same entry share the receipt of the first one
+    let target = BumpTarget::LedgerEntry(
+        ledger_keys[0]
+            .to_xdr_base64(Limits::none())
+            .map_err(MyError::ToXdrError)?
+    );
+    let mut contract_lock = network.contract_locks.lock(&id).await;
+
+    // Archived entries can't be extended, they must be restored first
+    let entry_ttl = read_ledger_entry_ttl(
+        key.clone(),
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    )
+    .await?;
+
+    if entry_ttl.archived {
+        return Err(MyError::LedgerEntryArchived(entry_ttl.key_xdr));
+    }
+
+    let old_ttl = entry_ttl.live_until_ledger.saturating_sub(entry_ttl.latest_ledger as u32);
+
+    // Already bumped, e.g. by the request we waited for
+    if old_ttl >= ledgers_to_extend.min(MAX_LEDGERS_TO_EXTEND) {
+        if let Some(receipt) = contract_lock.receipt_for(&target, ledgers_to_extend) {
+            return Ok(Json(receipt));
+        }
+        return Ok(Json(BumpReceipt {
+            transaction_hash: None,
+            ledger: None,
+            fee_charged: None,
+            old_ttl,
+            new_ttl: old_ttl,
+            live_until_ledger: entry_ttl.live_until_ledger,
+            code_old_ttl: None,
+            code_new_ttl: None,
+            code_live_until_ledger: None,
+        }));
     }
-    .run()
+
+    let transaction = send_footprint_transaction(
+        &state.ttl_pool,
+        &network,
+        ledger_keys,
+        FootprintOp::Extend(ledgers_to_extend)
+    )
     .await?;
 
-    // Read ledger ttl to return the new ttl
-    let (latest_ledger, live_until_ledger_seq) = read_ledger_ttl(
-        id,
-        state.rpc_url.clone(),
-        state.network_passphrase.clone(),
-        state.source_account.clone()
+    // Read ledger ttl to return the new live until ledger
+    let new_entry_ttl = read_ledger_entry_ttl(
+        key,
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
     )
     .await?;
 
-    let new_ttl = live_until_ledger_seq - latest_ledger as u32;
+    let receipt = BumpReceipt {
+        transaction_hash: Some(transaction.hash),
+        ledger: transaction.ledger,
+        fee_charged: transaction.fee_charged,
+        old_ttl,
+        new_ttl: new_entry_ttl.live_until_ledger.saturating_sub(new_entry_ttl.latest_ledger as u32),
+        live_until_ledger: new_entry_ttl.live_until_ledger,
+        code_old_ttl: None,
+        code_new_ttl: None,
+        code_live_until_ledger: None,
+    };
+
+    contract_lock.record(target, ledgers_to_extend, receipt.clone());
 
-    Ok(new_ttl.to_string())
+    Ok(Json(receipt))
 }
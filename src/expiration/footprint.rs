@@ -0,0 +1,137 @@
+use crate::error::MyError;
+use crate::network_profile::NetworkProfile;
+use crate::transactions::assemble::simulate_and_assemble;
+use crate::transactions::submit::{poll_transaction, submit_transaction, TrackedTransaction, STATUS_SUCCESS};
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{
+    ExtendFootprintTtlOp, ExtensionPoint, LedgerFootprint, LedgerKey, Memo, MuxedAccount,
+    Operation, OperationBody, Preconditions, RestoreFootprintOp, SequenceNumber,
+    SorobanResources, SorobanTransactionData, Transaction, TransactionExt, Uint256,
+};
+use soroban_cli::fee;
+use soroban_cli::rpc::Client;
+use soroban_cli::utils::sign_transaction;
+use sqlx::PgPool;
+
+// Same cap as the soroban CLI extend command
+pub const MAX_LEDGERS_TO_EXTEND: u32 = 535_679;
+
+// Kinds of the tracked footprint transactions
+pub const EXTEND_KIND: &str = "extend";
+pub const RESTORE_KIND: &str = "restore";
+
+// Footprint operations on ledger entries TTL
+pub enum FootprintOp {
+    // Extend the entries to live at least this number of ledgers
+    Extend(u32),
+    // Restore archived entries
+    Restore,
+}
+
+// Build, simulate, sign and send a transaction extending or restoring all the keys at once
+// The transaction is signed by a free signer of the network signer pool and tracked until it's final
+pub async fn send_footprint_transaction(
+    pool: &PgPool,
+    network: &NetworkProfile,
+    keys: Vec<LedgerKey>,
+    footprint_op: FootprintOp,
+) -> Result<TrackedTransaction, MyError> {
+
+    let client = Client::new(&network.rpc_url)?;
+
+    let kind = match footprint_op {
+        FootprintOp::Extend(_) => EXTEND_KIND,
+        FootprintOp::Restore => RESTORE_KIND,
+    };
+
+    let mut signer_guard = network.signer_pool.acquire().await;
+    let sequence = signer_guard.next_sequence(&client).await?;
+    let signer = signer_guard.signer();
+
+    let tx = build_footprint_transaction(
+        signer.key.verifying_key().to_bytes(),
+        sequence,
+        keys,
+        footprint_op
+    )?;
+
+    tracing::debug!("Sending footprint transaction with signer {}", signer.public_key);
+
+    // Simulation fills in the resources and fails if they exceed the network limits
+    let sent = async {
+        let tx = simulate_and_assemble(&client, tx).await?;
+        let envelope = sign_transaction(&signer.key, &tx, &network.network_passphrase)
+            .map_err(MyError::ToXdrError)?;
+        let transaction = submit_transaction(pool, network, &envelope, kind, &signer.public_key).await?;
+        poll_transaction(pool, network, &transaction.hash).await
+    }
+    .await;
+
+    match sent {
+        Ok(transaction) if transaction.status == STATUS_SUCCESS => Ok(transaction),
+        Ok(transaction) => {
+            // The sequence number may not have been consumed
+            signer_guard.reset_sequence();
+            Err(MyError::TransactionFailed(transaction.hash, transaction.error.unwrap_or_default()))
+        },
+        Err(e) => {
+            signer_guard.reset_sequence();
+            Err(e)
+        },
+    }
+}
+
+// Build the unsimulated footprint transaction of the source account (ed25519 public key)
+pub fn build_footprint_transaction(
+    source_account: [u8; 32],
+    sequence: i64,
+    keys: Vec<LedgerKey>,
+    footprint_op: FootprintOp,
+) -> Result<Transaction, MyError> {
+
+    // Extended entries are read only, restored entries are written
+    let (body, read_only, read_write) = match footprint_op {
+        FootprintOp::Extend(ledgers_to_extend) => (
+            OperationBody::ExtendFootprintTtl(ExtendFootprintTtlOp {
+                ext: ExtensionPoint::V0,
+                extend_to: u32::min(ledgers_to_extend, MAX_LEDGERS_TO_EXTEND),
+            }),
+            keys,
+            vec![],
+        ),
+        FootprintOp::Restore => (
+            OperationBody::RestoreFootprint(RestoreFootprintOp {
+                ext: ExtensionPoint::V0,
+            }),
+            vec![],
+            keys,
+        ),
+    };
+
+    Ok(Transaction {
+        source_account: MuxedAccount::Ed25519(Uint256(source_account)),
+        fee: fee::Args::default().fee,
+        seq_num: SequenceNumber(sequence),
+        cond: Preconditions::None,
+        memo: Memo::None,
+        operations: vec![Operation {
+            source_account: None,
+            body,
+        }]
+        .try_into()
+        .map_err(MyError::ToXdrError)?,
+        ext: TransactionExt::V1(SorobanTransactionData {
+            ext: ExtensionPoint::V0,
+            resources: SorobanResources {
+                footprint: LedgerFootprint {
+                    read_only: read_only.try_into().map_err(MyError::ToXdrError)?,
+                    read_write: read_write.try_into().map_err(MyError::ToXdrError)?,
+                },
+                instructions: 0,
+                read_bytes: 0,
+                write_bytes: 0,
+            },
+            resource_fee: 0,
+        }),
+    })
+}
@@ -0,0 +1,125 @@
+use crate::error::MyError;
+use serde::Deserialize;
+use soroban_cli::commands::contract::Durability;
+use soroban_cli::key;
+use sha2::{Sha256, Digest};
+use stellar_xdr::curr::{Hash, LedgerKey, Limits, WriteXdr};
+
+// Storage durability as passed in the query string
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageDurability {
+    #[default]
+    Persistent,
+    Temporary,
+}
+
+impl From<StorageDurability> for Durability {
+    fn from(durability: StorageDurability) -> Self {
+        match durability {
+            StorageDurability::Persistent => Durability::Persistent,
+            StorageDurability::Temporary => Durability::Temporary,
+        }
+    }
+}
+
+// Query parameters identifying a contract data entry
+// e.g. ?key=COUNTER&durability=temporary or ?key_xdr=AAAADwAAAAdDT1VOVEVSAA==
+// Without key nor key_xdr, the contract instance is targeted
+#[derive(Deserialize, Default)]
+pub struct LedgerKeyQuery {
+    pub key: Option<String>,
+    pub key_xdr: Option<String>,
+    #[serde(default)]
+    pub durability: StorageDurability,
+}
+
+impl LedgerKeyQuery {
+    // Build the soroban CLI key arguments of the entry owned by contract_id
+    pub fn key_args(&self, contract_id: String) -> Result<key::Args, MyError> {
+
+        if self.key.is_some() && self.key_xdr.is_some() {
+            return Err(MyError::ConflictingLedgerKeys);
+        }
+
+        Ok(key::Args {
+            contract_id: Some(contract_id),
+            key: self.key.clone().map(|key| vec![key]),
+            key_xdr: self.key_xdr.clone().map(|key_xdr| vec![key_xdr]),
+            wasm: None,
+            wasm_hash: None,
+            durability: self.durability.into(),
+        })
+    }
+}
+
+// Ledger key of a contract instance
+pub fn instance_ledger_key(contract_id: &str) -> Result<LedgerKey, MyError> {
+
+    let mut keys = LedgerKeyQuery::default()
+        .key_args(contract_id.to_string())?
+        .parse_keys()?;
+
+    Ok(keys.remove(0))
+}
+
+// Ledger key of a contract code (wasm), from its hex hash
+pub fn code_ledger_key(wasm_hash: &str) -> Result<LedgerKey, MyError> {
+
+    let mut keys = key::Args {
+        contract_id: None,
+        key: None,
+        key_xdr: None,
+        wasm: None,
+        wasm_hash: Some(wasm_hash.to_string()),
+        durability: Durability::Persistent,
+    }.parse_keys()?;
+
+    Ok(keys.remove(0))
+}
+
+// Base64 XDR of the SHA-256 of the ledger key XDR, the id of the entry expiration in Mercury
+pub fn hashed_ledger_key_xdr(ledger_key: &LedgerKey) -> Result<String, MyError> {
+
+    // Build the LedgerKey XDR (/!\ not base64)
+    let ledger_key_xdr = ledger_key
+        .to_xdr(Limits::none())
+        .map_err(MyError::ToXdrError)?;
+
+    // Hash the above XDR
+    let hashed_ledger_key_xdr = Hash(Sha256::digest(ledger_key_xdr).into());
+
+    // Build the XDR of the LedgerKey Hashed
+    hashed_ledger_key_xdr
+        .to_xdr_base64(Limits::none())
+        .map_err(MyError::ToXdrError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::stellar_xdr::curr::ReadXdr;
+
+    fn contract_id(byte: u8) -> String {
+        stellar_strkey::Contract([byte; 32]).to_string()
+    }
+
+    #[test]
+    fn hashed_ledger_key_is_the_sha256_of_the_key_xdr() {
+        let key = instance_ledger_key(&contract_id(1)).ok().unwrap();
+
+        let hashed = hashed_ledger_key_xdr(&key).ok().unwrap();
+        let hash = Hash::from_xdr_base64(hashed, Limits::none()).unwrap();
+
+        let key_xdr = key.to_xdr(Limits::none()).unwrap();
+        assert_eq!(hash.0, <[u8; 32]>::from(Sha256::digest(key_xdr)));
+    }
+
+    #[test]
+    fn hashed_ledger_keys_differ_by_contract() {
+        let key = instance_ledger_key(&contract_id(1)).ok().unwrap();
+        let other_key = instance_ledger_key(&contract_id(2)).ok().unwrap();
+
+        assert_ne!(hashed_ledger_key_xdr(&key).ok(), hashed_ledger_key_xdr(&other_key).ok());
+    }
+}
@@ -1,8 +1,10 @@
-use crate::AppState;
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
 use crate::error::MyError;
-use std::sync::Arc;
+use super::read_ledger::read_contract_ttl;
+use super::ledger_key::{instance_ledger_key, hashed_ledger_key_xdr};
+use serde::Serialize;
 use axum::{
-    extract::{State, Path},
+    extract::Path,
     Json
 };
 use graphql_client::{GraphQLQuery, Response as GraphQLResponse};
@@ -16,14 +18,137 @@ use graphql_client::{GraphQLQuery, Response as GraphQLResponse};
 )]
 pub struct LedgerInstanceExpiration;
 
+// Where an entry expiration comes from
+#[derive(Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ExpirationSource {
+    Mercury,
+    Rpc,
+}
+
+#[derive(Serialize)]
+pub struct EntryExpiration {
+    pub hash_xdr: String,
+    pub live_until_ledger: u32,
+    pub source: ExpirationSource,
+}
+
+// Handler Response: expiration of the contract instance and of its code
+#[derive(Serialize)]
+pub struct ContractExpiration {
+    pub contract_id: String,
+    // None when the contract couldn't be read from the RPC (archived or evicted)
+    pub latest_ledger: Option<i64>,
+    pub instance: EntryExpiration,
+    // None for the Stellar Asset Contracts, or when the contract couldn't be read from the RPC
+    pub code: Option<EntryExpiration>,
+}
+
 // Axum Handler to query ledger expiration
 pub async fn get_contract_instance_expiration(
-    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
     Path(encoded_hash_xdr): Path<String>
 ) -> Result<Json<ledger_instance_expiration::LedgerInstanceExpirationEntryExpirationByHashXdr>, MyError> {
 
-    let res;
+    let expiration_data = query_entry_expiration(&network, encoded_hash_xdr).await?;
+
+    Ok(Json(expiration_data))
+}
+
+// Axum Handler to get the expiration of a contract instance and of its code from its id
+// Mercury is queried first, the RPC is used for the entries Mercury has no record of
+pub async fn get_contract_expiration(
+    SelectedNetwork(network): SelectedNetwork,
+    Path(id): Path<String>
+) -> Result<Json<ContractExpiration>, MyError> {
+
+    stellar_strkey::Contract::from_string(id.as_str())
+        .map_err(|e| MyError::StringToContractConversionFailed(id.clone(), e))?;
+
+    let instance_hash_xdr = hashed_ledger_key_xdr(&instance_ledger_key(&id)?)?;
+    let instance_expiration = mercury_expiration(&network, &instance_hash_xdr).await;
+
+    // The code ledger key needs the wasm hash stored in the instance
+    let contract_ttl = match read_contract_ttl(
+        id.clone(),
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    )
+    .await {
+        Ok(contract_ttl) => contract_ttl,
+        // Mercury still knows the instance expiration
+        Err(e) => {
+            let Some(live_until_ledger) = instance_expiration else {
+                return Err(e);
+            };
+            tracing::debug!("Contract {} read from Mercury only: {}", id, e);
+            return Ok(Json(ContractExpiration {
+                contract_id: id,
+                latest_ledger: None,
+                instance: EntryExpiration {
+                    hash_xdr: instance_hash_xdr,
+                    live_until_ledger,
+                    source: ExpirationSource::Mercury,
+                },
+                code: None,
+            }));
+        }
+    };
+
+    let code = match (contract_ttl.code_ledger_key()?, contract_ttl.code_live_until_ledger) {
+        (Some(code_key), Some(code_live_until_ledger)) => {
+            let code_hash_xdr = hashed_ledger_key_xdr(&code_key)?;
+            let code_expiration = mercury_expiration(&network, &code_hash_xdr).await;
+            Some(entry_expiration(code_hash_xdr, code_expiration, code_live_until_ledger))
+        },
+        _ => None,
+    };
 
+    Ok(Json(ContractExpiration {
+        contract_id: id,
+        latest_ledger: Some(contract_ttl.latest_ledger),
+        instance: entry_expiration(instance_hash_xdr, instance_expiration, contract_ttl.instance_live_until_ledger),
+        code,
+    }))
+}
+
+// Expiration recorded by Mercury, None if Mercury has no record of the entry or can't be reached
+async fn mercury_expiration(network: &NetworkProfile, hash_xdr: &str) -> Option<u32> {
+
+    match query_entry_expiration(network, hash_xdr.to_string()).await {
+        Ok(expiration_data) => expiration_data.expiration.map(|expiration| expiration as u32),
+        Err(MyError::EmptyData | MyError::EmptyEntryExpiration) => None,
+        // The RPC answers instead
+        Err(e) => {
+            tracing::warn!("Failed to query the expiration of {} from Mercury: {}", hash_xdr, e);
+            None
+        },
+    }
+}
+
+// Expiration recorded by Mercury, or the one read from the RPC if there's none
+fn entry_expiration(hash_xdr: String, mercury_live_until_ledger: Option<u32>, rpc_live_until_ledger: u32) -> EntryExpiration {
+
+    let (live_until_ledger, source) = match mercury_live_until_ledger {
+        Some(live_until_ledger) => (live_until_ledger, ExpirationSource::Mercury),
+        None => (rpc_live_until_ledger, ExpirationSource::Rpc),
+    };
+
+    EntryExpiration {
+        hash_xdr,
+        live_until_ledger,
+        source,
+    }
+}
+
+// Query the expiration of a ledger entry tracked by Mercury from its hashed key XDR
+pub async fn query_entry_expiration(
+    network: &NetworkProfile,
+    encoded_hash_xdr: String,
+) -> Result<ledger_instance_expiration::LedgerInstanceExpirationEntryExpirationByHashXdr, MyError> {
+
+    let res;
     /* Scope to drop the mutex right after the query */
     {
         // GraphQL request variables
@@ -37,8 +162,8 @@ pub async fn get_contract_instance_expiration(
         // Post the GraphQL request
         let client = reqwest::Client::new();
         res = client
-                .post(format!("{}/graphql", state.mercury_graphql_endpoint))
-                .bearer_auth(state.mercury_jwt_token.lock().unwrap())
+                .post(format!("{}/graphql", network.mercury_graphql_endpoint))
+                .bearer_auth(network.mercury_jwt_token.lock().unwrap())
                 .json(&request_body)
                 .send()
                 .await?;
@@ -56,7 +181,7 @@ pub async fn get_contract_instance_expiration(
             .data.ok_or_else(|| MyError::EmptyData)?
             .entry_expiration_by_hash_xdr.ok_or_else(|| MyError::EmptyEntryExpiration)?;
         
-        return Ok(Json(expiration_data));
+        return Ok(expiration_data);
 
     }
 
@@ -1,8 +1,20 @@
-use crate::AppState;
+use crate::network_profile::SelectedNetwork;
 use crate::error::MyError;
-use std::sync::Arc;
-use axum::extract::{State, Path};
+use super::footprint::MAX_LEDGERS_TO_EXTEND;
+use axum::extract::{Path, Query};
 use axum::Json;
+use serde::Serialize;
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{
+    LedgerEntryData,
+    ContractDataEntry,
+    ScVal,
+    ScContractInstance,
+    ContractExecutable,
+    LedgerKey,
+    WriteXdr,
+    Limits,
+};
 use soroban_cli::commands::{
     network,
     config,
@@ -10,6 +22,67 @@ use soroban_cli::commands::{
 };
 use soroban_cli::key;
 use soroban_cli::rpc::Client;
+use super::ledger_key::{LedgerKeyQuery, instance_ledger_key, code_ledger_key};
+
+// TTL of a single ledger entry
+#[derive(Serialize)]
+pub struct LedgerEntryTtl {
+    pub latest_ledger: i64,
+    pub key_xdr: String,
+    pub live_until_ledger: u32,
+    pub archived: bool,
+}
+
+// TTLs of the two ledger entries a contract needs to stay alive
+#[derive(Serialize)]
+pub struct ContractTtl {
+    pub latest_ledger: i64,
+    // None for the Stellar Asset Contracts, which have no code entry
+    pub wasm_hash: Option<String>,
+    pub instance_live_until_ledger: u32,
+    pub code_live_until_ledger: Option<u32>,
+    pub instance_archived: bool,
+    pub code_archived: bool,
+}
+
+impl ContractTtl {
+    // True if the instance or the code has to be restored before the contract can be used
+    pub fn is_archived(&self) -> bool {
+        self.instance_archived || self.code_archived
+    }
+
+    // True if extending the instance and the code by this number of ledgers would change nothing
+    pub fn lives_at_least(&self, ledgers_to_extend: u32) -> bool {
+        let ledgers_to_extend = ledgers_to_extend.min(MAX_LEDGERS_TO_EXTEND);
+        !self.is_archived()
+            && self.instance_ttl() >= ledgers_to_extend
+            && self.code_ttl().is_none_or(|code_ttl| code_ttl >= ledgers_to_extend)
+    }
+
+    pub fn instance_ttl(&self) -> u32 {
+        self.instance_live_until_ledger.saturating_sub(self.latest_ledger as u32)
+    }
+
+    pub fn code_ttl(&self) -> Option<u32> {
+        self.code_live_until_ledger
+            .map(|code_live_until_ledger| code_live_until_ledger.saturating_sub(self.latest_ledger as u32))
+    }
+
+    // Ledger key of the contract code, if the contract runs a wasm
+    pub fn code_ledger_key(&self) -> Result<Option<LedgerKey>, MyError> {
+        self.wasm_hash
+            .as_deref()
+            .map(code_ledger_key)
+            .transpose()
+    }
+
+    // Ledger keys of the instance and of the code (if any) of the contract
+    pub fn ledger_keys(&self, contract_id: &str) -> Result<Vec<LedgerKey>, MyError> {
+        let mut keys = vec![instance_ledger_key(contract_id)?];
+        keys.extend(self.code_ledger_key()?);
+        Ok(keys)
+    }
+}
 
 pub async fn read_ledger_ttl(
     contract_id: String,
@@ -18,6 +91,32 @@ pub async fn read_ledger_ttl(
     source_account: String,
 ) -> Result<(i64, u32), MyError> {
 
+    let key = LedgerKeyQuery::default().key_args(contract_id.clone())?;
+
+    let entry_ttl = read_ledger_entry_ttl(
+        key,
+        rpc_url,
+        network_passphrase,
+        source_account
+    )
+    .await?;
+
+    if entry_ttl.archived {
+        return Err(MyError::ContractArchived(contract_id));
+    }
+
+    Ok((entry_ttl.latest_ledger, entry_ttl.live_until_ledger))
+
+}
+
+// Read the ttl of any contract data entry (instance, persistent or temporary storage)
+pub async fn read_ledger_entry_ttl(
+    key: key::Args,
+    rpc_url: String,
+    network_passphrase: String,
+    source_account: String,
+) -> Result<LedgerEntryTtl, MyError> {
+
     let config = config::Args {
         network: network::Args {
             rpc_url: Some(rpc_url),
@@ -32,38 +131,46 @@ pub async fn read_ledger_ttl(
         .get_network()
         .map_err(MyError::ConfigNetworkError)?;
 
-    let key = key::Args {
-        contract_id: Some(contract_id),
-        key: None,
-        key_xdr: None,
-        wasm: None,
-        wasm_hash: None,
-        durability: Durability::Persistent,
-    }.parse_keys()?;
+    let key = key.parse_keys()?;
 
     let client = Client::new(&network.rpc_url)?;
 
     let full_ledger_entries = client.get_full_ledger_entries(&key).await?;
-    let latest_ledger = full_ledger_entries.latest_ledger;
 
-    let live_until_ledger_seq = full_ledger_entries.entries[0].live_until_ledger_seq;
+    // Expired temporary entries are not returned by the RPC
+    let entry = full_ledger_entries
+        .entries
+        .first()
+        .ok_or(MyError::LedgerEntryNotFound)?;
 
-    Ok((latest_ledger, live_until_ledger_seq))
+    let key_xdr = entry.key
+        .to_xdr_base64(Limits::none())
+        .map_err(MyError::ToXdrError)?;
+
+    Ok(LedgerEntryTtl {
+        latest_ledger: full_ledger_entries.latest_ledger,
+        key_xdr,
+        live_until_ledger: entry.live_until_ledger_seq,
+        archived: is_archived(full_ledger_entries.latest_ledger, entry.live_until_ledger_seq),
+    })
 
 }
 
-pub async fn read_ledger_ttl_handler(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<String>
-) -> Result<Json<(i64, u32)>, MyError> {
+// Read the ttl of both the contract instance and the contract code (wasm) it points to
+pub async fn read_contract_ttl(
+    contract_id: String,
+    rpc_url: String,
+    network_passphrase: String,
+    source_account: String,
+) -> Result<ContractTtl, MyError> {
 
     let config = config::Args {
         network: network::Args {
-            rpc_url: Some(state.rpc_url.clone()),
-            network_passphrase: Some(state.network_passphrase.clone()),
+            rpc_url: Some(rpc_url),
+            network_passphrase: Some(network_passphrase),
             network: None,
         },
-        source_account: state.source_account.clone(),
+        source_account,
         ..Default::default()
     };
 
@@ -71,8 +178,8 @@ pub async fn read_ledger_ttl_handler(
         .get_network()
         .map_err(MyError::ConfigNetworkError)?;
 
-    let key = key::Args {
-        contract_id: Some(id),
+    let instance_key = key::Args {
+        contract_id: Some(contract_id),
         key: None,
         key_xdr: None,
         wasm: None,
@@ -82,11 +189,161 @@ pub async fn read_ledger_ttl_handler(
 
     let client = Client::new(&network.rpc_url)?;
 
-    let full_ledger_entries = client.get_full_ledger_entries(&key).await?;
-    let latest_ledger = full_ledger_entries.latest_ledger;
+    let instance_entries = client.get_full_ledger_entries(&instance_key).await?;
+    let instance_entry = instance_entries
+        .entries
+        .first()
+        .ok_or(MyError::LedgerEntryNotFound)?;
+
+    // The instance entry holds the hash of the wasm it executes
+    let wasm_hash = extract_wasm_hash(&instance_entry.val)?;
+
+    let mut latest_ledger = instance_entries.latest_ledger;
+    let mut code_live_until_ledger = None;
+
+    if let Some(wasm_hash) = &wasm_hash {
+
+        let code_key = vec![code_ledger_key(wasm_hash)?];
+
+        let code_entries = client.get_full_ledger_entries(&code_key).await?;
+        let code_entry = code_entries
+            .entries
+            .first()
+            .ok_or(MyError::LedgerEntryNotFound)?;
+
+        latest_ledger = code_entries.latest_ledger;
+        code_live_until_ledger = Some(code_entry.live_until_ledger_seq);
+    }
+
+    Ok(ContractTtl {
+        latest_ledger,
+        wasm_hash,
+        instance_live_until_ledger: instance_entry.live_until_ledger_seq,
+        code_live_until_ledger,
+        instance_archived: is_archived(latest_ledger, instance_entry.live_until_ledger_seq),
+        code_archived: code_live_until_ledger.is_some_and(|code_live_until_ledger| is_archived(latest_ledger, code_live_until_ledger)),
+    })
+
+}
+
+// A persistent entry whose live until ledger has passed is archived and must be restored
+fn is_archived(latest_ledger: i64, live_until_ledger: u32) -> bool {
+    i64::from(live_until_ledger) < latest_ledger
+}
+
+// Retrieve the wasm hash (hex) from a contract instance ledger entry
+// None for the Stellar Asset Contracts, built in the host
+pub fn extract_wasm_hash(entry: &LedgerEntryData) -> Result<Option<String>, MyError> {
+
+    match entry {
+        LedgerEntryData::ContractData(ContractDataEntry {
+            val: ScVal::ContractInstance(ScContractInstance { executable, .. }),
+            ..
+        }) => match executable {
+            ContractExecutable::Wasm(hash) => Ok(Some(hash.to_string())),
+            ContractExecutable::StellarAsset => Ok(None),
+        },
+        _ => Err(MyError::HashRetrievalFailed),
+    }
+
+}
+
+pub async fn read_ledger_ttl_handler(
+    SelectedNetwork(network): SelectedNetwork,
+    Path(id): Path<String>
+) -> Result<Json<ContractTtl>, MyError> {
+
+    let contract_ttl = read_contract_ttl(
+        id,
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    )
+    .await?;
+
+    Ok(Json(contract_ttl))
+
+}
+
+pub async fn read_ledger_entry_ttl_handler(
+    SelectedNetwork(network): SelectedNetwork,
+    Path(id): Path<String>,
+    Query(key_query): Query<LedgerKeyQuery>,
+) -> Result<Json<LedgerEntryTtl>, MyError> {
+
+    let entry_ttl = read_ledger_entry_ttl(
+        key_query.key_args(id)?,
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    )
+    .await?;
+
+    Ok(Json(entry_ttl))
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::stellar_xdr::curr::{ContractDataDurability, ExtensionPoint, Hash, ScAddress};
+
+    fn instance_entry(executable: ContractExecutable) -> LedgerEntryData {
+        LedgerEntryData::ContractData(ContractDataEntry {
+            ext: ExtensionPoint::V0,
+            contract: ScAddress::Contract(Hash([1; 32])),
+            key: ScVal::LedgerKeyContractInstance,
+            durability: ContractDataDurability::Persistent,
+            val: ScVal::ContractInstance(ScContractInstance { executable, storage: None }),
+        })
+    }
+
+    #[test]
+    fn extract_wasm_hash_of_wasm_contract() {
+        let entry = instance_entry(ContractExecutable::Wasm(Hash([0xab; 32])));
+        assert_eq!(extract_wasm_hash(&entry).ok().flatten(), Some("ab".repeat(32)));
+    }
+
+    #[test]
+    fn extract_wasm_hash_of_stellar_asset_contract() {
+        let entry = instance_entry(ContractExecutable::StellarAsset);
+        assert!(matches!(extract_wasm_hash(&entry), Ok(None)));
+    }
+
+    fn contract_ttl(instance_live_until_ledger: u32, code_live_until_ledger: Option<u32>) -> ContractTtl {
+        ContractTtl {
+            latest_ledger: 1_000,
+            wasm_hash: code_live_until_ledger.map(|_| "ab".repeat(32)),
+            instance_live_until_ledger,
+            code_live_until_ledger,
+            instance_archived: i64::from(instance_live_until_ledger) < 1_000,
+            code_archived: code_live_until_ledger.is_some_and(|ledger| i64::from(ledger) < 1_000),
+        }
+    }
+
+    #[test]
+    fn lives_at_least_checks_the_instance_and_the_code() {
+        let ttl = contract_ttl(1_500, Some(1_200));
+        assert!(ttl.lives_at_least(200));
+        assert!(!ttl.lives_at_least(300));
+    }
 
-    let live_until_ledger_seq = full_ledger_entries.entries[0].live_until_ledger_seq;
+    #[test]
+    fn lives_at_least_without_code_entry() {
+        let ttl = contract_ttl(1_500, None);
+        assert!(ttl.lives_at_least(500));
+        assert!(!ttl.lives_at_least(501));
+    }
 
-    Ok(Json((latest_ledger, live_until_ledger_seq)))
+    #[test]
+    fn archived_contracts_never_live_long_enough() {
+        let ttl = contract_ttl(1_500, Some(900));
+        assert!(!ttl.lives_at_least(0));
+    }
 
+    #[test]
+    fn lives_at_least_caps_the_extension() {
+        let ttl = contract_ttl(1_000 + MAX_LEDGERS_TO_EXTEND, None);
+        assert!(ttl.lives_at_least(u32::MAX));
+    }
 }
@@ -0,0 +1,88 @@
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
+use crate::error::MyError;
+use crate::balance_monitor::check_manual_bump_funds;
+use crate::AppState;
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use super::read_ledger::{read_contract_ttl, ContractTtl};
+use super::ledger_key::instance_ledger_key;
+use super::footprint::{send_footprint_transaction, FootprintOp};
+
+// Query parameters of the restore handler
+#[derive(Deserialize)]
+pub struct RestoreQuery {
+    pub ledgers_to_extend: Option<u32>,
+}
+
+// Handler to restore an archived contract instance and its contract code (wasm)
+// Optionally extend both entries once restored: ?ledgers_to_extend=
+pub async fn restore_contract_instance(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Path(id): Path<String>,
+    Query(restore_query): Query<RestoreQuery>,
+) -> Result<Json<ContractTtl>, MyError> {
+
+    check_manual_bump_funds(&network)?;
+
+    let contract_ttl = restore_contract(&state.ttl_pool, &network, id, restore_query.ledgers_to_extend).await?;
+
+    Ok(Json(contract_ttl))
+}
+
+// Run the restore footprint flow for every archived entry of the contract
+// (instance and code) with the service's source account
+// Waits for the bumps and restores of the contract already in flight
+pub async fn restore_contract(
+    pool: &PgPool,
+    network: &NetworkProfile,
+    id: String,
+    ledgers_to_extend: Option<u32>,
+) -> Result<ContractTtl, MyError> {
+
+    let _contract_lock = network.contract_locks.lock(&id).await;
+
+    let contract_ttl = read_contract_ttl(
+        id.clone(),
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    )
+    .await?;
+
+    let mut archived_keys = Vec::new();
+    if contract_ttl.instance_archived {
+        archived_keys.push(instance_ledger_key(&id)?);
+    }
+    if contract_ttl.code_archived {
+        archived_keys.extend(contract_ttl.code_ledger_key()?);
+    }
+
+    if !archived_keys.is_empty() {
+        tracing::debug!("Restoring {} archived ledger entries of contract {}", archived_keys.len(), id);
+        send_footprint_transaction(pool, network, archived_keys, FootprintOp::Restore).await?;
+    }
+
+    // Extend both entries once restored, unless they already live long enough
+    if let Some(ledgers_to_extend) = ledgers_to_extend.filter(|ledgers| !contract_ttl.lives_at_least(*ledgers)) {
+        send_footprint_transaction(
+            pool,
+            network,
+            contract_ttl.ledger_keys(&id)?,
+            FootprintOp::Extend(ledgers_to_extend)
+        )
+        .await?;
+    }
+
+    // Read ledger ttl to return the restored state
+    read_contract_ttl(
+        id,
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    )
+    .await
+}
@@ -1,55 +1,32 @@
 use crate::AppState;
+use crate::admin::AdminAuth;
+use crate::network_profile::SelectedNetwork;
 use crate::error::MyError;
-use std::sync::Arc;
+use crate::postgres::subscriptions::{record_subscription, EXPIRATION_SUBSCRIPTION};
 use axum::{
-    extract::{State, Path},
+    extract::{Path, State},
     Json
 };
-use stellar_xdr::curr as stellar_xdr;
-use stellar_xdr::{
-    ScAddress,
-    ScVal,
-    LedgerKey,
-    LedgerKeyContractData,
-    ContractDataDurability,
-    WriteXdr,
-    Limits,
-    Hash,
-};
-use sha2::{Sha256, Digest};
+use std::sync::Arc;
 use serde_json::json;
 use super::read_ledger::read_ledger_ttl;
+use super::ledger_key::{instance_ledger_key, hashed_ledger_key_xdr};
 
 // Axum Handler for subscribing to contract expiration
+// Admin only: every subscription is indexed (and paid for) on the Mercury account of the API
 pub async fn subscribe_contract_expiration(
+    _admin: AdminAuth,
     State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
     Path(id): Path<String>
 ) -> Result<Json<serde_json::Value>, MyError> {
     
     // Build the LedgerKey knowing the contract id
-    let contract = stellar_strkey::Contract::from_string(id.as_str())
+    stellar_strkey::Contract::from_string(id.as_str())
         .map_err(|e| MyError::StringToContractConversionFailed(id.clone(), e))?;
+    let ledger_key = instance_ledger_key(&id)?;
 
-    let ledger_key = LedgerKey::ContractData(
-        LedgerKeyContractData {
-            contract: ScAddress::Contract(Hash(contract.0)),
-            key: ScVal::LedgerKeyContractInstance,
-            durability: ContractDataDurability::Persistent,
-        }
-    );
-
-    // Build the LedgerKey XDR (/!\ not base64)
-    let ledger_key_xdr = ledger_key
-        .to_xdr(Limits::none())
-        .map_err(MyError::ToXdrError)?;
-
-    // Hash the above XDR
-    let hashed_ledger_key_xdr = Hash(Sha256::digest(ledger_key_xdr).into());
-
-    // Build the XDR of the LedgerKey Hashed
-    let hash_xdr = hashed_ledger_key_xdr
-        .to_xdr_base64(Limits::none())
-        .map_err(MyError::ToXdrError)?;
+    let hash_xdr = hashed_ledger_key_xdr(&ledger_key)?;
     tracing::debug!("Xdr of LedgerKey Hashed is : {:#?}", hash_xdr);
 
     // Create the request body
@@ -58,8 +35,8 @@ pub async fn subscribe_contract_expiration(
     });
 
     let client = reqwest::Client::new();
-    let res = client.post(format!("{}/expiration", state.mercury_backend_endpoint))
-        .bearer_auth(state.mercury_jwt_token.lock().unwrap())
+    let res = client.post(format!("{}/expiration", network.mercury_backend_endpoint))
+        .bearer_auth(network.mercury_jwt_token.lock().unwrap())
         .json(&request_body)
         .send()
         .await?;
@@ -67,17 +44,30 @@ pub async fn subscribe_contract_expiration(
 
     if res.status().is_success() {
 
+        // Keep track of the subscription
+        let mercury_response = res.text().await?;
+        let subscription = record_subscription(
+            &state.ttl_pool,
+            &network,
+            EXPIRATION_SUBSCRIPTION,
+            &id,
+            Some(&hash_xdr),
+            &[],
+            &mercury_response
+        ).await?;
+
         let (current_ledger, ledger_ttl) = read_ledger_ttl(
             id.clone(),
-            state.rpc_url.clone(),
-            state.network_passphrase.clone(),
-            state.source_account.clone()
+            network.rpc_url.clone(),
+            network.network_passphrase.clone(),
+            network.source_account.clone()
         ).await?;
 
         tracing::debug!("SUCCESSFULLY SUBSCRIBE TO CONTRACT EXPIRATION TRACKING: {}", id);
         
         let ret_val = json!({
             "status": "CONTRACT_INSTANCE_SUBSCRIBED",
+            "subscription_id": subscription.id,
             "contract_id": id,
             "hash_xdr": hash_xdr,
             "current_ledger": current_ledger,
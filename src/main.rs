@@ -1,52 +1,115 @@
-use axum::routing::{Router, get, post};
+// MyError wraps the (large) soroban_cli errors as-is
+#![allow(clippy::result_large_err)]
+
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{Router, get, post},
+};
 use sqlx::PgPool;
 use anyhow::anyhow;
 use shuttle_secrets::SecretStore;
 use tower_http::cors::CorsLayer;
 use http::{Method, HeaderName, HeaderValue};
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tower::ServiceExt;
 use events::{
     get_publish::get_publish_events,
     get_deploy::get_deploy_events,
     get_claim::get_claim_events,
+    subscribe_contract_events::subscribe_contract_events,
+    query_events::get_events,
+    activity::get_activity,
+    stats::get_stats,
 };
 use expiration::{
     subscribe_ledger_expiration::subscribe_contract_expiration,
-    query_ledger_expiration::get_contract_instance_expiration,
-    read_ledger::read_ledger_ttl_handler,
-    extend_ttl::bump_contract_instance,
+    query_ledger_expiration::{get_contract_instance_expiration, get_contract_expiration},
+    read_ledger::{read_ledger_ttl_handler, read_ledger_entry_ttl_handler},
+    extend_ttl::{bump_contract_instance, bump_ledger_entry},
+    restore::restore_contract_instance,
+    batch_extend::bump_contract_instances,
+};
+use postgres::db_communication::{retrieve, add, retrieve_one, update, delete};
+use postgres::ownership::get_owner_challenge;
+use postgres::subscriptions::{list_subscriptions, unsubscribe};
+use postgres::event_index::search;
+use network_profile::{backfill_default_network, network_prefixed_uri, NetworkProfile, DEFAULT_NETWORK_NAME};
+use balance_monitor::get_source_account_balance;
+use utils::xdr::{encode_scval, decode_scval, build_ledger_key};
+use utils::strkey::convert_contract_id;
+use transactions::{
+    relay::{build_relay_bump, submit_relay_bump},
+    submit::{submit_signed_transaction, get_transaction_status},
+    sponsor::{submit_sponsored_transaction, get_sponsor_quota, set_sponsor_quota},
+    deploy::build_deploy_transaction,
+    publish::build_publish_transactions,
 };
-use postgres::db_communication::{retrieve, add};
 
 mod events {
     pub mod get_deploy;
     pub mod get_publish;
     pub mod get_claim;
+    pub mod subscribe_contract_events;
+    pub mod query_events;
+    pub mod event_data;
+    pub mod activity;
+    pub mod stats;
 }
 mod expiration {
     pub mod extend_ttl;
+    pub mod ledger_key;
+    pub mod restore;
+    pub mod auto_bump;
+    pub mod batch_extend;
+    pub mod footprint;
+    pub mod contract_lock;
     pub mod read_ledger;
     pub mod subscribe_ledger_expiration;
     pub mod query_ledger_expiration;
 }
+mod transactions {
+    pub mod envelope;
+    pub mod assemble;
+    pub mod submit;
+    pub mod relay;
+    pub mod sponsor;
+    pub mod invoke;
+    pub mod deploy;
+    pub mod publish;
+}
 mod postgres {
     pub mod db_communication; 
+    pub mod ownership;
+    pub mod reconciler;
+    pub mod auto_register;
+    pub mod subscriptions;
+    pub mod event_index;
+}
+mod utils {
+    pub mod xdr;
+    pub mod strkey;
 }
 mod error;
+mod network_profile;
+mod signer_pool;
+mod balance_monitor;
 mod update_token;
+mod admin;
 
 #[derive(Clone)]
 struct AppState {
-    mercury_jwt_token: Arc<Mutex<String>>,
-    mercury_backend_endpoint: String,
-    mercury_graphql_endpoint: String,
-    mercury_id: String,
-    mercury_pwd: String,
-    rpc_url: String,
-    network_passphrase: String,
-    source_account: String,
+    networks: HashMap<String, Arc<NetworkProfile>>,
+    default_network: String,
     ttl_pool: PgPool,
     front_end_url: String,
+    auto_bump_batch: bool,
+    auto_register_deploys: bool,
+    owner_challenges: postgres::ownership::OwnerChallenges,
+    // Bearer token of the admin routes, closed if not set
+    admin_token: Option<String>,
 }
 
 #[shuttle_runtime::main]
@@ -63,64 +126,143 @@ async fn main(
      */
 
     // Retrieve the secret variables
-    let Some(mercury_backend_endpoint) = secret_store.get("MERCURY_BACKEND_ENDPOINT") else {
-        return Err(anyhow!("MERCURY_BACKEND_ENDPOINT not set in Secrets.toml file").into());
-    };
-    let Some(mercury_graphql_endpoint) = secret_store.get("MERCURY_GRAPHQL_ENDPOINT") else {
-        return Err(anyhow!("MERCURY_GRAPHQL_ENDPOINT not set in Secrets.toml file").into());
-    };
-    let Some(mercury_id) = secret_store.get("MERCURY_EMAIL") else {
-        return Err(anyhow!("MERCURY_EMAIL not set in Secrets.toml file").into());
-    };
-    let Some(mercury_pwd) = secret_store.get("MERCURY_PASSWORD") else {
-        return Err(anyhow!("MERCURY_PASSWORD not set in Secrets.toml file").into());
-    };
-    let Some(rpc_url) = secret_store.get("RPC_URL") else {
-        return Err(anyhow!("RPC_URL not set in Secrets.toml file").into());
-    };
-    let Some(network_passphrase) = secret_store.get("NETWORK_PASSPHRASE") else {
-        return Err(anyhow!("NETWORK_PASSPHRASE not set in Secrets.toml file").into());
-    };
-    let Some(source_account) = secret_store.get("SOURCE_ACCOUNT") else {
-        return Err(anyhow!("SOURCE_ACCOUNT not set in Secrets.toml file").into());
-    };
     let Some(front_end_url) = secret_store.get("FRONT_END_URL") else {
         return Err(anyhow!("FRONT_END_URL not set in Secrets.toml file").into());
     };
 
+    // Optional: several network profiles, e.g. NETWORKS = 'testnet,mainnet'
+    // Each profile reads its secrets prefixed by its name (TESTNET_RPC_URL, MAINNET_RPC_URL...)
+    // Without NETWORKS, a single "default" profile reads the unprefixed secrets (RPC_URL...)
+    let network_names: Vec<String> = match secret_store.get("NETWORKS") {
+        Some(names) => names
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect(),
+        None => vec![DEFAULT_NETWORK_NAME.to_string()],
+    };
+    if network_names.is_empty() {
+        return Err(anyhow!("NETWORKS is set but lists no network").into());
+    }
+
+    let mut networks = HashMap::new();
+    for name in &network_names {
+        let profile = NetworkProfile::from_secrets(&secret_store, name)?;
+        networks.insert(name.clone(), Arc::new(profile));
+    }
+
+    // Network used by requests without ?network=, the first one listed if DEFAULT_NETWORK isn't set
+    let default_network = secret_store
+        .get("DEFAULT_NETWORK")
+        .unwrap_or_else(|| network_names[0].clone());
+    if !networks.contains_key(&default_network) {
+        return Err(anyhow!("DEFAULT_NETWORK {} is not listed in NETWORKS", default_network).into());
+    }
+
+    // Optional: bump the automatic_bump contracts in batch transactions
+    let auto_bump_batch = secret_store
+        .get("AUTO_BUMP_BATCH")
+        .is_some_and(|batch| batch == "true");
+
+    // Optional: register the contracts deployed through SmartDeploy into contracts_ttl
+    let auto_register_deploys = secret_store
+        .get("AUTO_REGISTER_DEPLOYS")
+        .is_some_and(|register| register == "true");
+
     // Create the AppState
     let state = Arc::new(AppState {
-        mercury_jwt_token: Arc::new(Mutex::new("".to_string())),
-        mercury_backend_endpoint,
-        mercury_graphql_endpoint,
-        mercury_id,
-        mercury_pwd,
-        rpc_url,
-        network_passphrase,
-        source_account,
+        networks,
+        default_network,
         ttl_pool,
         front_end_url,
+        auto_bump_batch,
+        auto_register_deploys,
+        owner_challenges: Default::default(),
+        admin_token: secret_store.get("ADMIN_TOKEN").filter(|token| !token.is_empty()),
     });
 
     let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST])
+        .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
         .allow_origin(state.front_end_url.parse::<HeaderValue>().unwrap())
-        .allow_headers([HeaderName::from_static("content-type")]);
+        .allow_headers([
+            HeaderName::from_static("content-type"),
+            HeaderName::from_static("authorization"),
+            HeaderName::from_static("x-owner"),
+            HeaderName::from_static("x-owner-challenge"),
+            HeaderName::from_static("x-owner-signature"),
+        ]);
+
+    for network in state.networks.values() {
+        update_token::renew_jwt_cron_job(network.clone()).await;
+        expiration::auto_bump::auto_bump_cron_job(state.clone(), network.clone()).await;
+        balance_monitor::balance_monitor_cron_job(network.clone()).await;
+        postgres::reconciler::reconciler_cron_job(state.clone(), network.clone()).await;
+        postgres::event_index::event_index_cron_job(state.clone(), network.clone()).await;
+        if state.auto_register_deploys {
+            postgres::auto_register::auto_register_cron_job(state.clone(), network.clone()).await;
+        }
+    }
 
-    update_token::renew_jwt_cron_job(state.clone()).await;
+    // Rows written before NETWORKS was set belong to the default network
+    if !state.networks.contains_key(DEFAULT_NETWORK_NAME) {
+        backfill_default_network(&state.ttl_pool, &state.default_network)
+            .await
+            .map_err(|e| anyhow!("Failed to move the default network rows: {}", e))?;
+    }
 
     // Create the routes of the API
     let router = Router::new()
         .route("/get_publish", get(get_publish_events)).layer(cors.clone())
         .route("/get_deploy", get(get_deploy_events)).layer(cors.clone())
         .route("/get_claim", get(get_claim_events)).layer(cors.clone())
+        .route("/events", get(get_events)).layer(cors.clone())
+        .route("/activity", get(get_activity)).layer(cors.clone())
+        .route("/stats", get(get_stats)).layer(cors.clone())
+        .route("/search", get(search)).layer(cors.clone())
         .route("/subscribe_contract_expiration/:id", get(subscribe_contract_expiration)).layer(cors.clone())
+        .route("/subscribe_contract_events/:id", get(subscribe_contract_events)).layer(cors.clone())
+        .route("/subscriptions", get(list_subscriptions)).layer(cors.clone())
+        .route("/subscriptions/:id", axum::routing::delete(unsubscribe)).layer(cors.clone())
         .route("/query_ledger_expiration/:encoded_hash_xdr", get(get_contract_instance_expiration)).layer(cors.clone())
+        .route("/contracts/:id/expiration", get(get_contract_expiration)).layer(cors.clone())
         .route("/read_ledger_ttl/:id", get(read_ledger_ttl_handler)).layer(cors.clone())
         .route("/bump_contract_instance/:id/:ledgers_to_extend", get(bump_contract_instance)).layer(cors.clone())
+        .route("/restore_contract_instance/:id", get(restore_contract_instance)).layer(cors.clone())
+        .route("/bump_contract_instances", post(bump_contract_instances)).layer(cors.clone())
+        .route("/read_ledger_entry_ttl/:id", get(read_ledger_entry_ttl_handler)).layer(cors.clone())
+        .route("/bump_ledger_entry/:id/:ledgers_to_extend", get(bump_ledger_entry)).layer(cors.clone())
+        .route("/tx/submit", post(submit_signed_transaction)).layer(cors.clone())
+        .route("/tx/:hash", get(get_transaction_status)).layer(cors.clone())
+        .route("/relay/bump_contract_instance/:id/:ledgers_to_extend", get(build_relay_bump)).layer(cors.clone())
+        .route("/relay/submit", post(submit_relay_bump)).layer(cors.clone())
+        .route("/relay/status/:hash", get(get_transaction_status)).layer(cors.clone())
+        .route("/build/deploy", post(build_deploy_transaction)).layer(cors.clone())
+        .route("/build/publish", post(build_publish_transactions)).layer(cors.clone())
+        .route("/sponsor/submit", post(submit_sponsored_transaction)).layer(cors.clone())
+        .route("/sponsor/quota/:account", get(get_sponsor_quota)).layer(cors.clone())
+        .route("/admin/sponsor_quotas", post(set_sponsor_quota)).layer(cors.clone())
+        .route("/xdr/scval/encode", post(encode_scval)).layer(cors.clone())
+        .route("/xdr/scval/decode", post(decode_scval)).layer(cors.clone())
+        .route("/xdr/ledger_key/:id", get(build_ledger_key)).layer(cors.clone())
+        .route("/strkey/contract/:id", get(convert_contract_id)).layer(cors.clone())
+        .route("/admin/source_account", get(get_source_account_balance)).layer(cors.clone())
+        .route("/contracts_ttl/challenge/:owner", get(get_owner_challenge)).layer(cors.clone())
         .route("/contracts_ttl", post(add)).layer(cors.clone())
-        .route("/contracts_ttl", get(retrieve)).layer(cors)
-        .with_state(state);
+        .route("/contracts_ttl", get(retrieve)).layer(cors.clone())
+        .route("/contracts_ttl/:id", get(retrieve_one).patch(update).delete(delete)).layer(cors)
+        .with_state(state.clone());
+
+    // Every route is also served under /<network>/..., same as ?network=<network>
+    let network_routes = router.clone();
+    let router = router.fallback(move |mut request: Request| async move {
+        match network_prefixed_uri(request.uri(), |name| state.networks.contains_key(name)) {
+            Some(uri) => {
+                *request.uri_mut() = uri;
+                network_routes.oneshot(request).await.into_response()
+            },
+            None => StatusCode::NOT_FOUND.into_response(),
+        }
+    });
 
     Ok(router.into())
 }
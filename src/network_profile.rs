@@ -0,0 +1,245 @@
+use crate::AppState;
+use crate::error::MyError;
+use crate::signer_pool::SignerPool;
+use crate::balance_monitor::BalanceSnapshot;
+use crate::expiration::contract_lock::ContractLocks;
+use crate::events::stats::RegistryStats;
+use anyhow::anyhow;
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::{request::Parts, Uri},
+};
+use serde::Deserialize;
+use shuttle_secrets::SecretStore;
+use sqlx::PgPool;
+use std::sync::{Arc, Mutex};
+
+// Name of the network profile built from the unprefixed secrets, when NETWORKS isn't set
+pub const DEFAULT_NETWORK_NAME: &str = "default";
+
+// Everything the API needs to talk to one Stellar network (RPC + Mercury indexer)
+pub struct NetworkProfile {
+    pub name: String,
+    pub mercury_jwt_token: Arc<Mutex<String>>,
+    pub mercury_backend_endpoint: String,
+    pub mercury_graphql_endpoint: String,
+    pub mercury_id: String,
+    pub mercury_pwd: String,
+    pub rpc_url: String,
+    pub network_passphrase: String,
+    pub source_account: String,
+    // SmartDeploy registry contract (C...), needed to build the registry transactions
+    pub smartdeploy_contract_id: Option<String>,
+    // Source account and channel accounts signing the service transactions
+    pub signer_pool: Arc<SignerPool>,
+    // Bumps in flight, by contract id
+    pub contract_locks: ContractLocks,
+    // Last balance of the signer pool accounts, loaded by the balance monitor
+    pub balance: Mutex<BalanceSnapshot>,
+    // Total balance (in stroops) under which an alert is raised
+    pub balance_alert_floor: i64,
+    // Total balance (in stroops) under which manual bumps are refused
+    pub manual_bump_reserve: i64,
+    pub alert_webhook_url: Option<String>,
+    // Default quota of fee-bumped transactions per user account (overridden in sponsor_quotas)
+    pub sponsor_max_transactions: i32,
+    // Default quota of fees (in stroops) paid per user account
+    pub sponsor_max_fee: i64,
+    // Last statistics computed by /stats
+    pub stats: Mutex<Option<RegistryStats>>,
+}
+
+impl NetworkProfile {
+    // Build the profile from the secrets prefixed by the network name
+    // e.g. TESTNET_RPC_URL for the network "testnet", RPC_URL for the default network
+    pub fn from_secrets(secret_store: &SecretStore, name: &str) -> Result<Self, anyhow::Error> {
+
+        let prefix = if name == DEFAULT_NETWORK_NAME {
+            String::new()
+        } else {
+            format!("{}_", name.to_uppercase())
+        };
+
+        let get_secret = |key: &str| {
+            let secret_name = format!("{}{}", prefix, key);
+            secret_store
+                .get(&secret_name)
+                .ok_or_else(|| anyhow!("{} not set in Secrets.toml file", secret_name))
+        };
+
+        let source_account = get_secret("SOURCE_ACCOUNT")?;
+
+        // Optional: funded channel accounts, e.g. CHANNEL_ACCOUNTS = 'SA...,SB...'
+        let mut accounts = vec![source_account.clone()];
+        if let Ok(channel_accounts) = get_secret("CHANNEL_ACCOUNTS") {
+            accounts.extend(
+                channel_accounts
+                    .split(',')
+                    .map(|account| account.trim().to_string())
+                    .filter(|account| !account.is_empty())
+            );
+        }
+        let signer_pool = SignerPool::new(&accounts)
+            .map_err(|e| anyhow!("Invalid signer of network {}: {}", name, e))?;
+
+        // Optional: balance thresholds in XLM, e.g. BALANCE_ALERT_FLOOR = '100'
+        let get_xlm_secret = |key: &str, default: f64| -> Result<i64, anyhow::Error> {
+            let xlm = match get_secret(key) {
+                Ok(xlm) => xlm.parse::<f64>().map_err(|e| anyhow!("Invalid {}: {}", key, e))?,
+                Err(_) => default,
+            };
+            Ok((xlm * 10_000_000.0) as i64)
+        };
+
+        // Optional: default sponsorship quota, e.g. SPONSOR_MAX_TRANSACTIONS = '3'
+        let sponsor_max_transactions = match get_secret("SPONSOR_MAX_TRANSACTIONS") {
+            Ok(max) => max.parse::<i32>().map_err(|e| anyhow!("Invalid SPONSOR_MAX_TRANSACTIONS: {}", e))?,
+            Err(_) => 3,
+        };
+
+        Ok(NetworkProfile {
+            name: name.to_string(),
+            mercury_jwt_token: Arc::new(Mutex::new("".to_string())),
+            mercury_backend_endpoint: get_secret("MERCURY_BACKEND_ENDPOINT")?,
+            mercury_graphql_endpoint: get_secret("MERCURY_GRAPHQL_ENDPOINT")?,
+            mercury_id: get_secret("MERCURY_EMAIL")?,
+            mercury_pwd: get_secret("MERCURY_PASSWORD")?,
+            rpc_url: get_secret("RPC_URL")?,
+            network_passphrase: get_secret("NETWORK_PASSPHRASE")?,
+            source_account,
+            smartdeploy_contract_id: get_secret("SMARTDEPLOY_CONTRACT_ID").ok(),
+            signer_pool: Arc::new(signer_pool),
+            contract_locks: ContractLocks::default(),
+            balance: Mutex::new(BalanceSnapshot::default()),
+            balance_alert_floor: get_xlm_secret("BALANCE_ALERT_FLOOR", 100.0)?,
+            manual_bump_reserve: get_xlm_secret("MANUAL_BUMP_RESERVE", 20.0)?,
+            alert_webhook_url: get_secret("ALERT_WEBHOOK_URL").ok(),
+            sponsor_max_transactions,
+            sponsor_max_fee: get_xlm_secret("SPONSOR_MAX_FEE", 5.0)?,
+            stats: Mutex::new(None),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct NetworkQuery {
+    network: Option<String>,
+}
+
+// Axum extractor selecting the network profile of the request: ?network=<name>
+// (the /<name>/... paths are rewritten to it by network_prefixed_uri)
+// Falls back to the default network when the query parameter is missing
+pub struct SelectedNetwork(pub Arc<NetworkProfile>);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for SelectedNetwork {
+    type Rejection = MyError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+
+        let network_name = Query::<NetworkQuery>::try_from_uri(&parts.uri)
+            .ok()
+            .and_then(|Query(query)| query.network)
+            .unwrap_or_else(|| state.default_network.clone());
+
+        state
+            .networks
+            .get(&network_name)
+            .cloned()
+            .map(SelectedNetwork)
+            .ok_or(MyError::UnknownNetwork(network_name))
+    }
+}
+
+// Rewrite /<network>/<route>?<query> into /<route>?<query>&network=<network>, None if the first path
+// segment isn't a network
+pub fn network_prefixed_uri(uri: &Uri, is_network: impl Fn(&str) -> bool) -> Option<Uri> {
+
+    let path = uri.path().strip_prefix('/')?;
+    let (network, route) = match path.split_once('/') {
+        Some((network, route)) => (network, route),
+        None => (path, ""),
+    };
+    if !is_network(network) {
+        return None;
+    }
+
+    // The path segment wins over a network query parameter
+    let mut query = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty() && *pair != "network" && !pair.starts_with("network="))
+        .collect::<Vec<_>>();
+    let network_pair = format!("network={}", network);
+    query.push(&network_pair);
+
+    format!("/{}?{}", route, query.join("&")).parse().ok()
+}
+
+// Tables scoped by network, with the column identifying a row within a network
+// (None when a network has a single row)
+const NETWORK_TABLES: [(&str, Option<&str>); 6] = [
+    ("contracts_ttl", Some("contract_id")),
+    ("transactions", Some("hash")),
+    ("sponsor_quotas", Some("account")),
+    ("mercury_subscriptions", Some("id")),
+    ("registry_events", Some("event_id")),
+    ("auto_register_cursors", None),
+];
+
+// Move the rows written under the unprefixed "default" profile to the default network once NETWORKS is set
+// Rows already present on the default network are kept, the "default" ones are left as is
+pub async fn backfill_default_network(pool: &PgPool, default_network: &str) -> Result<(), MyError> {
+
+    for (table, key) in NETWORK_TABLES {
+
+        let same_row = key
+            .map(|key| format!("AND moved.{key} = old.{key}"))
+            .unwrap_or_default();
+
+        let moved = sqlx::query(&format!("UPDATE {table} AS old SET network = $1
+                                WHERE old.network = $2
+                                AND NOT EXISTS (SELECT 1 FROM {table} AS moved WHERE moved.network = $1 {same_row})
+                            "))
+                            .bind(default_network)
+                            .bind(DEFAULT_NETWORK_NAME)
+                            .execute(pool)
+                            .await?;
+
+        if moved.rows_affected() > 0 {
+            tracing::info!("Moved {} rows of {} from network {} to {}", moved.rows_affected(), table, DEFAULT_NETWORK_NAME, default_network);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(uri: &str) -> Option<String> {
+        network_prefixed_uri(&uri.parse().unwrap(), |name| name == "testnet" || name == "mainnet")
+            .map(|uri| uri.to_string())
+    }
+
+    #[test]
+    fn network_path_segment_becomes_the_query_parameter() {
+        assert_eq!(rewrite("/mainnet/get_deploy").as_deref(), Some("/get_deploy?network=mainnet"));
+        assert_eq!(rewrite("/testnet/contracts_ttl/C1?limit=5").as_deref(), Some("/contracts_ttl/C1?limit=5&network=testnet"));
+        assert_eq!(rewrite("/testnet").as_deref(), Some("/?network=testnet"));
+    }
+
+    #[test]
+    fn network_path_segment_wins_over_the_query_parameter() {
+        assert_eq!(rewrite("/mainnet/stats?network=testnet&q=a").as_deref(), Some("/stats?q=a&network=mainnet"));
+    }
+
+    #[test]
+    fn other_paths_are_not_rewritten() {
+        assert_eq!(rewrite("/get_deploy"), None);
+        assert_eq!(rewrite("/futurenet/get_deploy"), None);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::AppState;
+use crate::network_profile::NetworkProfile;
+use crate::error::MyError;
+use crate::events::event_data::event_address;
+use crate::events::query_events::{query_events, EventsQuery};
+use crate::expiration::read_ledger::read_contract_ttl;
+use std::sync::Arc;
+use tokio::time::{Duration, sleep};
+
+// Function to register the contracts deployed through SmartDeploy into contracts_ttl every 5 minutes
+// (AUTO_REGISTER_DEPLOYS = 'true'). They're registered with automatic_bump off, owned by their deployer.
+pub async fn auto_register_cron_job(state: Arc<AppState>, network: Arc<NetworkProfile>) {
+
+    tokio::spawn(async move {
+
+        loop {
+
+            tracing::debug!("Registering deployed contracts of network {}", network.name);
+
+            if let Err(e) = register_deployed_contracts(&state, &network).await {
+                tracing::error!("Error while registering deployed contracts of network {}: {}", network.name, e);
+            }
+
+            sleep(Duration::from_secs(5 * 60)).await;
+        }
+    });
+}
+
+// Register the contracts of the Deploy events emitted by the registry since the last registered ledger
+// Contracts deleted from contracts_ttl aren't registered again
+async fn register_deployed_contracts(state: &AppState, network: &NetworkProfile) -> Result<(), MyError> {
+
+    let Some(registry_id) = network.smartdeploy_contract_id.clone() else {
+        return Ok(());
+    };
+
+    let last_ledger = sqlx::query_scalar::<_, i64>("SELECT last_ledger FROM auto_register_cursors WHERE network = $1")
+        .bind(&network.name)
+        .fetch_optional(&state.ttl_pool)
+        .await?;
+
+    let events_query = EventsQuery {
+        topic1: Some("{\"string\":\"Deploy\"}".to_string()),
+        contract: Some(registry_id),
+        from_ledger: last_ledger.map(|ledger| ledger + 1),
+        ..Default::default()
+    };
+    let mut deploy_events = match query_events(network, &events_query).await {
+        Ok(deploy_events) => deploy_events,
+        Err(MyError::EmptyNodes | MyError::EmptyEventByTopic) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    // Events without ledger can't be placed before or after the cursor
+    deploy_events.retain(|event| event.ledger.is_some());
+    deploy_events.sort_by_key(|event| event.ledger);
+
+    let mut registered_ledger = last_ledger;
+
+    for event in deploy_events {
+
+        let ledger = event.ledger.unwrap_or_default();
+
+        let Some(contract_id) = event_address(&event.data, "contract_id") else {
+            registered_ledger = Some(ledger);
+            continue;
+        };
+
+        // Retried at the next run, along with the later events
+        let contract_ttl = match read_contract_ttl(
+            contract_id.clone(),
+            network.rpc_url.clone(),
+            network.network_passphrase.clone(),
+            network.source_account.clone()
+        ).await {
+            Ok(contract_ttl) => contract_ttl,
+            Err(e) => {
+                tracing::error!("Error while reading ttl of deployed contract {}: {}", contract_id, e);
+                // Events before the cursor are filtered out, so it doesn't move back
+                registered_ledger = Some(ledger - 1);
+                break;
+            }
+        };
+
+        tracing::debug!("Registering deployed contract {}", contract_id);
+
+        // The frontend may have registered it in the meantime
+        sqlx::query("INSERT INTO contracts_ttl (network, contract_id, automatic_bump, live_until_ttl, owner, checked_at_ledger)
+                        VALUES ($1, $2, FALSE, $3, $4, $5)
+                        ON CONFLICT (network, contract_id) DO NOTHING;
+                    ")
+                    .bind(&network.name)
+                    .bind(&contract_id)
+                    .bind(contract_ttl.instance_live_until_ledger as i32)
+                    .bind(event_address(&event.data, "deployer"))
+                    .bind(contract_ttl.latest_ledger as i32)
+                    .execute(&state.ttl_pool)
+                    .await?;
+
+        registered_ledger = Some(ledger);
+    }
+
+    if let Some(registered_ledger) = registered_ledger.filter(|ledger| Some(*ledger) != last_ledger) {
+        sqlx::query("INSERT INTO auto_register_cursors (network, last_ledger)
+                        VALUES ($1, $2)
+                        ON CONFLICT (network) DO UPDATE SET last_ledger = $2;
+                    ")
+                    .bind(&network.name)
+                    .bind(registered_ledger)
+                    .execute(&state.ttl_pool)
+                    .await?;
+    }
+
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use crate::AppState;
 use crate::error::MyError;
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
+use crate::admin::AdminAuth;
+use super::ownership::{authorize_owner, OwnerProof};
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -12,9 +15,11 @@ use sqlx::FromRow;
 
 pub async fn retrieve(
     State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
 ) -> Result<(StatusCode, Json<Vec<Ttl>>), MyError> {
 
-    let contracts_ttl = sqlx::query_as::<_, Ttl>("SELECT * FROM contracts_ttl")
+    let contracts_ttl = sqlx::query_as::<_, Ttl>("SELECT * FROM contracts_ttl WHERE network = $1")
+        .bind(&network.name)
         .fetch_all(&state.ttl_pool)
         .await?;
 
@@ -24,29 +29,181 @@ pub async fn retrieve(
 
 pub async fn add(
     State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    owner_proof: OwnerProof,
+    admin: Option<AdminAuth>,
     Json(ttl_data): Json<Ttl>,
 ) -> Result<impl IntoResponse, impl IntoResponse> {
 
-    match sqlx::query_as::<_, Ttl> ("INSERT INTO contracts_ttl (contract_id, automatic_bump, live_until_ttl)
-                                        VALUES ($1, $2, $3)
-                                        ON CONFLICT (contract_id) DO UPDATE
-                                        SET automatic_bump = $2, live_until_ttl = $3
-                                        RETURNING contract_id, automatic_bump, live_until_ttl;
+    validate_contract_id(&ttl_data.contract_id)?;
+
+    // Only the owner can overwrite the settings of a contract
+    let current_owner = current_owner(&state, &network, &ttl_data.contract_id).await?.flatten();
+    let owner = authorize_owner(&state, &network, &ttl_data.contract_id, current_owner, &owner_proof, admin.is_some()).await?;
+
+    // The owner may have changed since it was checked
+    match sqlx::query_as::<_, Ttl> ("INSERT INTO contracts_ttl (network, contract_id, automatic_bump, live_until_ttl, owner)
+                                        VALUES ($1, $2, $3, $4, $5)
+                                        ON CONFLICT (network, contract_id) DO UPDATE
+                                        SET automatic_bump = $3, live_until_ttl = $4, owner = $5
+                                        WHERE contracts_ttl.owner IS NULL OR contracts_ttl.owner = $5
+                                        RETURNING network, contract_id, automatic_bump, live_until_ttl, owner, checked_at_ledger, out_of_sync, sync_error;
                                     ")
-                                    .bind(ttl_data.contract_id)
+                                    .bind(&network.name)
+                                    .bind(&ttl_data.contract_id)
                                     .bind(ttl_data.automatic_bump)
                                     .bind(ttl_data.live_until_ttl)
-                                    .fetch_one(&state.ttl_pool)
+                                    .bind(owner)
+                                    .fetch_optional(&state.ttl_pool)
                                     .await
     {
-        Ok(data) => Ok((StatusCode::CREATED, Json(data))),
+        Ok(Some(data)) => Ok((StatusCode::CREATED, Json(data))),
+        Ok(None) => Err(owner_changed(&ttl_data.contract_id)),
         Err(e) => Err((StatusCode::BAD_REQUEST, e.to_string())),
     }
 }
 
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct Ttl {
+    // Set from the selected network, ignored in the request body
+    #[serde(default)]
+    pub network: String,
     pub contract_id: String,
     pub automatic_bump: bool,
     pub live_until_ttl: i32,
-}
\ No newline at end of file
+    // Account or contract allowed to change the settings, set by the API
+    #[serde(default)]
+    pub owner: Option<String>,
+    // Ledger at which live_until_ttl was last read from the chain, set by the API
+    #[serde(default)]
+    pub checked_at_ledger: Option<i32>,
+    // live_until_ttl differed from the chain at the last check
+    #[serde(default)]
+    pub out_of_sync: bool,
+    // Why the contract couldn't be checked at the last check, set by the API
+    #[serde(default)]
+    pub sync_error: Option<String>,
+}
+
+// Body of the PATCH request
+#[derive(Deserialize)]
+pub struct TtlPatch {
+    pub automatic_bump: bool,
+}
+
+// Reject anything but a contract strkey (C...)
+fn validate_contract_id(contract_id: &str) -> Result<(), (StatusCode, String)> {
+    match stellar_strkey::Contract::from_string(contract_id) {
+        Ok(_) => Ok(()),
+        Err(e) => Err((StatusCode::BAD_REQUEST, MyError::StringToContractConversionFailed(contract_id.to_string(), e).to_string())),
+    }
+}
+
+fn not_found(contract_id: &str) -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, format!("Contract {} not found in contracts_ttl", contract_id))
+}
+
+// The row changed between the owner check and the write
+fn owner_changed(contract_id: &str) -> (StatusCode, String) {
+    (StatusCode::CONFLICT, format!("Contract {} was changed or deleted by another request, try again", contract_id))
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, MyError::SqlxError(e).to_string())
+}
+
+// Owner of the contract: None if the contract isn't registered, Some(None) if it has no owner yet
+async fn current_owner(
+    state: &AppState,
+    network: &NetworkProfile,
+    contract_id: &str,
+) -> Result<Option<Option<String>>, (StatusCode, String)> {
+
+    sqlx::query_scalar::<_, Option<String>>("SELECT owner FROM contracts_ttl WHERE network = $1 AND contract_id = $2")
+        .bind(&network.name)
+        .bind(contract_id)
+        .fetch_optional(&state.ttl_pool)
+        .await
+        .map_err(internal_error)
+}
+
+pub async fn retrieve_one(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Path(contract_id): Path<String>,
+) -> Result<(StatusCode, Json<Ttl>), (StatusCode, String)> {
+
+    validate_contract_id(&contract_id)?;
+
+    let contract_ttl = sqlx::query_as::<_, Ttl>("SELECT * FROM contracts_ttl WHERE network = $1 AND contract_id = $2")
+        .bind(&network.name)
+        .bind(&contract_id)
+        .fetch_optional(&state.ttl_pool)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| not_found(&contract_id))?;
+
+    Ok((StatusCode::OK, Json(contract_ttl)))
+}
+
+// Toggle the automatic bump of a contract
+pub async fn update(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Path(contract_id): Path<String>,
+    owner_proof: OwnerProof,
+    admin: Option<AdminAuth>,
+    Json(ttl_patch): Json<TtlPatch>,
+) -> Result<(StatusCode, Json<Ttl>), (StatusCode, String)> {
+
+    validate_contract_id(&contract_id)?;
+
+    let current_owner = current_owner(&state, &network, &contract_id).await?
+        .ok_or_else(|| not_found(&contract_id))?;
+    let owner = authorize_owner(&state, &network, &contract_id, current_owner, &owner_proof, admin.is_some()).await?;
+
+    // The owner may have changed (or the contract been deleted) since it was checked
+    let contract_ttl = sqlx::query_as::<_, Ttl>("UPDATE contracts_ttl SET automatic_bump = $3, owner = $4
+                                        WHERE network = $1 AND contract_id = $2 AND (owner IS NULL OR owner = $4)
+                                        RETURNING network, contract_id, automatic_bump, live_until_ttl, owner, checked_at_ledger, out_of_sync, sync_error;
+                                    ")
+                                    .bind(&network.name)
+                                    .bind(&contract_id)
+                                    .bind(ttl_patch.automatic_bump)
+                                    .bind(owner)
+                                    .fetch_optional(&state.ttl_pool)
+                                    .await
+                                    .map_err(internal_error)?
+                                    .ok_or_else(|| owner_changed(&contract_id))?;
+
+    Ok((StatusCode::OK, Json(contract_ttl)))
+}
+
+pub async fn delete(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Path(contract_id): Path<String>,
+    owner_proof: OwnerProof,
+    admin: Option<AdminAuth>,
+) -> Result<StatusCode, (StatusCode, String)> {
+
+    validate_contract_id(&contract_id)?;
+
+    let current_owner = current_owner(&state, &network, &contract_id).await?
+        .ok_or_else(|| not_found(&contract_id))?;
+    let owner = authorize_owner(&state, &network, &contract_id, current_owner, &owner_proof, admin.is_some()).await?;
+
+    let deleted = sqlx::query("DELETE FROM contracts_ttl WHERE network = $1 AND contract_id = $2 AND (owner IS NULL OR owner = $3)")
+        .bind(&network.name)
+        .bind(&contract_id)
+        .bind(owner)
+        .execute(&state.ttl_pool)
+        .await
+        .map_err(internal_error)?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(owner_changed(&contract_id));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,257 @@
+use crate::AppState;
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
+use crate::error::MyError;
+use crate::events::event_data::{event_address, event_bytes, event_string, RegistryEvent};
+use crate::events::query_events::registry_events;
+use axum::{
+    extract::{Query, State},
+    Json
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Sha256, Digest};
+use sqlx::FromRow;
+use std::sync::Arc;
+use tokio::time::{Duration, sleep};
+
+// 20 results by default, 100 at most
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 100;
+// Matching events grouped into the results, best matches first
+const MAX_MATCHES: i64 = 1000;
+
+// Row of the registry_events table
+#[derive(Serialize, FromRow)]
+pub struct IndexedEvent {
+    pub kind: String,
+    pub name: Option<String>,
+    pub published_name: Option<String>,
+    pub actor: Option<String>,
+    pub contract_id: Option<String>,
+    pub wasm_hash: Option<String>,
+    pub ledger: Option<i64>,
+    pub tx_hash: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+// Handler Response: a deployed contract, or a published contract with its versions
+#[derive(Serialize)]
+pub struct SearchResult {
+    // Deployed contract (C...), None for a published contract
+    pub contract_id: Option<String>,
+    // Deployed name of a contract, published name otherwise
+    pub name: Option<String>,
+    pub published_name: Option<String>,
+    // Matching events, best matches first
+    pub events: Vec<IndexedEvent>,
+}
+
+// Function to copy the decoded registry events into registry_events every 5 minutes
+pub async fn event_index_cron_job(state: Arc<AppState>, network: Arc<NetworkProfile>) {
+
+    tokio::spawn(async move {
+
+        loop {
+
+            tracing::debug!("Indexing registry events of network {}", network.name);
+
+            if let Err(e) = index_registry_events(&state, &network).await {
+                tracing::error!("Error while indexing registry events of network {}: {}", network.name, e);
+            }
+
+            sleep(Duration::from_secs(5 * 60)).await;
+        }
+    });
+}
+
+async fn index_registry_events(state: &AppState, network: &NetworkProfile) -> Result<(), MyError> {
+
+    for registry_event in RegistryEvent::ALL {
+
+        // publish, deploy or claim
+        let kind = registry_event.topic().to_lowercase();
+
+        for event in registry_events(network, registry_event.topic()).await? {
+
+            let event_id = hex::encode(Sha256::digest(
+                json!([kind, event.tx_hash, event.data]).to_string()
+            ));
+
+            let indexed_event = IndexedEvent {
+                kind: kind.clone(),
+                name: event_string(&event.data, registry_event.name_field()),
+                published_name: event_string(&event.data, "published_name"),
+                actor: event_address(&event.data, registry_event.actor_field()),
+                contract_id: event_address(&event.data, "contract_id"),
+                wasm_hash: event_bytes(&event.data, "hash").map(hex::encode),
+                ledger: event.ledger,
+                tx_hash: event.tx_hash,
+            };
+
+            let search_text = [
+                &indexed_event.name,
+                &indexed_event.published_name,
+                &indexed_event.actor,
+                &indexed_event.contract_id,
+                &indexed_event.wasm_hash,
+            ]
+            .into_iter()
+            .flatten()
+            .map(|field| field.to_lowercase())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+            // Already indexed events are left as is
+            sqlx::query("INSERT INTO registry_events (network, event_id, kind, name, published_name, actor, contract_id, wasm_hash, ledger, tx_hash, search_text)
+                            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                            ON CONFLICT (network, event_id) DO NOTHING;
+                        ")
+                        .bind(&network.name)
+                        .bind(&event_id)
+                        .bind(&indexed_event.kind)
+                        .bind(&indexed_event.name)
+                        .bind(&indexed_event.published_name)
+                        .bind(&indexed_event.actor)
+                        .bind(&indexed_event.contract_id)
+                        .bind(&indexed_event.wasm_hash)
+                        .bind(indexed_event.ledger)
+                        .bind(&indexed_event.tx_hash)
+                        .bind(&search_text)
+                        .execute(&state.ttl_pool)
+                        .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Handler to search the published and deployed contracts by name, author, wasm hash or contract id
+// Best matches first, the events of the same contract are grouped together
+pub async fn search(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Query(search_query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchResult>>, MyError> {
+
+    let q = search_query.q.trim().to_lowercase();
+    if q.is_empty() {
+        return Err(MyError::InvalidArgument("q must not be empty".to_string()));
+    }
+
+    // Match q literally
+    let pattern = format!("%{}%", q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+
+    let matches = sqlx::query_as::<_, IndexedEvent>("SELECT kind, name, published_name, actor, contract_id, wasm_hash, ledger, tx_hash
+                                        FROM registry_events
+                                        WHERE network = $1 AND search_text LIKE $2
+                                        ORDER BY similarity(search_text, $3) DESC, ledger DESC NULLS LAST
+                                        LIMIT $4
+                                    ")
+                                    .bind(&network.name)
+                                    .bind(&pattern)
+                                    .bind(&q)
+                                    .bind(MAX_MATCHES)
+                                    .fetch_all(&state.ttl_pool)
+                                    .await?;
+
+    let limit = search_query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    Ok(Json(group_results(matches, limit)))
+}
+
+// Group the events by deployed contract, or by published name for the publishes,
+// keeping the order of the best match of each group
+fn group_results(matches: Vec<IndexedEvent>, limit: usize) -> Vec<SearchResult> {
+
+    let mut results: Vec<SearchResult> = Vec::new();
+
+    for event in matches {
+
+        let position = results.iter().position(|result| match &event.contract_id {
+            Some(contract_id) => result.contract_id.as_ref() == Some(contract_id),
+            None => result.contract_id.is_none() && result.published_name == event.published_name,
+        });
+
+        match position {
+            Some(position) => {
+                let result = &mut results[position];
+                if result.name.is_none() {
+                    result.name = event.name.clone();
+                }
+                if result.published_name.is_none() {
+                    result.published_name = event.published_name.clone();
+                }
+                result.events.push(event);
+            },
+            None if results.len() < limit => results.push(SearchResult {
+                contract_id: event.contract_id.clone(),
+                name: event.name.clone(),
+                published_name: event.published_name.clone(),
+                events: vec![event],
+            }),
+            None => (),
+        }
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: &str, name: &str, contract_id: Option<&str>) -> IndexedEvent {
+        IndexedEvent {
+            kind: kind.to_string(),
+            name: Some(name.to_string()),
+            published_name: Some("counter".to_string()),
+            actor: None,
+            contract_id: contract_id.map(String::from),
+            wasm_hash: None,
+            ledger: None,
+            tx_hash: None,
+        }
+    }
+
+    #[test]
+    fn events_are_grouped_by_contract_then_published_name() {
+        let matches = vec![
+            event("deploy", "my_counter", Some("C1")),
+            event("publish", "counter", None),
+            event("claim", "my_counter", Some("C1")),
+            event("deploy", "other_counter", Some("C2")),
+            event("publish", "counter", None),
+        ];
+
+        let results = group_results(matches, 10);
+
+        let groups = results
+            .iter()
+            .map(|result| (result.contract_id.as_deref(), result.name.as_deref(), result.events.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(groups, vec![
+            (Some("C1"), Some("my_counter"), 2),
+            (None, Some("counter"), 2),
+            (Some("C2"), Some("other_counter"), 1),
+        ]);
+    }
+
+    #[test]
+    fn results_are_limited_to_groups() {
+        let matches = vec![
+            event("deploy", "a", Some("C1")),
+            event("deploy", "b", Some("C2")),
+            event("claim", "a", Some("C1")),
+        ];
+
+        let results = group_results(matches, 1);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].events.len(), 2);
+    }
+}
@@ -0,0 +1,379 @@
+use crate::AppState;
+use crate::error::MyError;
+use crate::network_profile::NetworkProfile;
+use crate::events::event_data::{event_address, RegistryEvent};
+use crate::events::query_events::{query_events, EventsQuery};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, State},
+    http::{request::Parts, StatusCode},
+    Json,
+};
+use ed25519_dalek::{Signature, VerifyingKey};
+use rand::RngCore;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// A challenge must be signed within 5 minutes
+const CHALLENGE_VALIDITY_SECS: u64 = 5 * 60;
+// Pending challenges kept at most per owner, the oldest one is dropped beyond
+const MAX_PENDING_CHALLENGES_PER_OWNER: usize = 5;
+
+static ISSUED_CHALLENGES: AtomicU64 = AtomicU64::new(0);
+
+// Pending ownership challenges, by nonce
+pub type OwnerChallenges = Arc<Mutex<HashMap<String, OwnerChallenge>>>;
+
+// Handler Response: message to sign with the owner wallet
+#[derive(Serialize, Clone)]
+pub struct OwnerChallenge {
+    pub owner: String,
+    // Hex nonce identifying the challenge, sent back in X-Owner-Challenge
+    pub nonce: String,
+    pub challenge: String,
+    // Unix timestamp
+    pub expires_at: u64,
+    // Order in which the challenges were issued
+    #[serde(skip)]
+    issued: u64,
+}
+
+// Proof of ownership sent with the requests changing a contract:
+// X-Owner (G...), X-Owner-Challenge (nonce of the challenge)
+// and X-Owner-Signature (hex ed25519 signature of the challenge)
+pub struct OwnerProof {
+    pub owner: Option<String>,
+    pub nonce: Option<String>,
+    pub signature: Option<String>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for OwnerProof {
+    type Rejection = MyError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+
+        let header = |name: &str| {
+            parts.headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+
+        Ok(OwnerProof {
+            owner: header("x-owner"),
+            nonce: header("x-owner-challenge"),
+            signature: header("x-owner-signature"),
+        })
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+// Handler to get a challenge to sign, proving the control of the owner account
+// Each call creates a new challenge, the pending ones stay valid
+pub async fn get_owner_challenge(
+    State(state): State<Arc<AppState>>,
+    Path(owner): Path<String>,
+) -> Result<Json<OwnerChallenge>, MyError> {
+
+    stellar_strkey::ed25519::PublicKey::from_string(&owner)
+        .map_err(|e| MyError::StringToAccountConversionFailed(owner.clone(), e))?;
+
+    let challenge = new_challenge(&state.owner_challenges, owner);
+
+    Ok(Json(challenge))
+}
+
+fn new_challenge(challenges: &OwnerChallenges, owner: String) -> OwnerChallenge {
+
+    let mut nonce = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let nonce = hex::encode(nonce);
+
+    let challenge = OwnerChallenge {
+        challenge: format!("SmartDeploy API ownership of {} nonce {}", owner, nonce),
+        owner,
+        nonce: nonce.clone(),
+        expires_at: now() + CHALLENGE_VALIDITY_SECS,
+        issued: ISSUED_CHALLENGES.fetch_add(1, Ordering::Relaxed),
+    };
+
+    let mut challenges = challenges.lock().unwrap();
+    challenges.retain(|_, challenge| challenge.expires_at > now());
+
+    // Requests for other owners can't use up the challenges of this one
+    let mut owner_challenges = challenges
+        .values()
+        .filter(|pending| pending.owner == challenge.owner)
+        .map(|pending| (pending.issued, pending.nonce.clone()))
+        .collect::<Vec<_>>();
+    owner_challenges.sort();
+    let excess = (owner_challenges.len() + 1).saturating_sub(MAX_PENDING_CHALLENGES_PER_OWNER);
+    for (_, oldest_nonce) in owner_challenges.into_iter().take(excess) {
+        challenges.remove(&oldest_nonce);
+    }
+
+    challenges.insert(nonce, challenge.clone());
+
+    challenge
+}
+
+// Check the signature of the proof challenge, which must be a pending challenge of the owner
+// The challenge is used once: it's removed when its signature is valid
+fn verify_challenge(challenges: &OwnerChallenges, owner: &str, proof: &OwnerProof) -> bool {
+
+    let (Some(proof_owner), Some(nonce), Some(signature)) = (&proof.owner, &proof.nonce, &proof.signature) else {
+        return false;
+    };
+    if proof_owner != owner {
+        return false;
+    }
+
+    let mut challenges = challenges.lock().unwrap();
+    let Some(challenge) = challenges.get(nonce) else {
+        return false;
+    };
+    if challenge.owner != owner || challenge.expires_at <= now() {
+        return false;
+    }
+
+    let Ok(owner_key) = stellar_strkey::ed25519::PublicKey::from_string(owner) else {
+        return false;
+    };
+    let Ok(verifying_key) = VerifyingKey::from_bytes(&owner_key.0) else {
+        return false;
+    };
+    let Some(signature) = hex::decode(signature).ok().and_then(|bytes| Signature::from_slice(&bytes).ok()) else {
+        return false;
+    };
+
+    if verifying_key.verify_strict(challenge.challenge.as_bytes(), &signature).is_err() {
+        return false;
+    }
+
+    challenges.remove(nonce);
+    true
+}
+
+// Owner allowed to change the contract settings, who must sign its challenge:
+// the current owner, or for a new (or unowned) contract the deployer recorded in its Deploy event
+// Owners which can't sign a challenge (no Deploy event, contract deployer) are left to the admin,
+// who can assign a contract without deployer to the signer of a challenge
+pub async fn authorize_owner(
+    state: &AppState,
+    network: &NetworkProfile,
+    contract_id: &str,
+    current_owner: Option<String>,
+    proof: &OwnerProof,
+    admin: bool,
+) -> Result<Option<String>, (StatusCode, String)> {
+
+    let deployer = match current_owner {
+        Some(_) => None,
+        None => deployer_of(&state.ttl_pool, network, contract_id)
+            .await
+            .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?,
+    };
+
+    check_owner(&state.owner_challenges, contract_id, current_owner.or(deployer), proof, admin)
+}
+
+fn check_owner(
+    challenges: &OwnerChallenges,
+    contract_id: &str,
+    expected_owner: Option<String>,
+    proof: &OwnerProof,
+    admin: bool,
+) -> Result<Option<String>, (StatusCode, String)> {
+
+    let forbidden = |reason: String| (StatusCode::FORBIDDEN, MyError::NotOwner(contract_id.to_string(), reason).to_string());
+
+    match expected_owner {
+        // Only accounts (G...) can sign a challenge
+        Some(owner) if !owner.starts_with('G') => match admin {
+            true => Ok(Some(owner)),
+            false => Err(forbidden(format!("it's owned by {}, which can't sign a challenge, ask the admin", owner))),
+        },
+        Some(owner) if verify_challenge(challenges, &owner, proof) => Ok(Some(owner)),
+        Some(owner) => Err(forbidden(format!("sign a challenge of {}", owner))),
+        None if admin => match &proof.owner {
+            Some(owner) if verify_challenge(challenges, owner, proof) => Ok(Some(owner.clone())),
+            _ => Ok(None),
+        },
+        None => Err(forbidden("no Deploy event records its deployer, ask the admin".to_string())),
+    }
+}
+
+// Deployer of the contract recorded in the Deploy event emitted by the SmartDeploy registry
+// Looked up in the registry_events index, then in Mercury for the contracts deployed since the last indexing
+pub async fn deployer_of(pool: &PgPool, network: &NetworkProfile, contract_id: &str) -> Result<Option<String>, MyError> {
+
+    let Some(registry_id) = network.smartdeploy_contract_id.clone() else {
+        return Ok(None);
+    };
+
+    let indexed = sqlx::query_scalar::<_, Option<String>>("SELECT actor FROM registry_events
+                                        WHERE network = $1 AND kind = 'deploy' AND contract_id = $2
+                                        LIMIT 1
+                                    ")
+                                    .bind(&network.name)
+                                    .bind(contract_id)
+                                    .fetch_optional(pool)
+                                    .await?;
+    if let Some(deployer) = indexed {
+        return Ok(deployer);
+    }
+
+    let events_query = EventsQuery {
+        topic1: Some(format!("{{\"string\":\"{}\"}}", RegistryEvent::Deploy.topic())),
+        contract: Some(registry_id),
+        ..Default::default()
+    };
+    let events = match query_events(network, &events_query).await {
+        Ok(events) => events,
+        Err(MyError::EmptyNodes | MyError::EmptyEventByTopic) => Vec::new(),
+        Err(e) => return Err(e),
+    };
+
+    let deployer = events
+        .iter()
+        .find(|event| event_address(&event.data, "contract_id").as_deref() == Some(contract_id))
+        .and_then(|event| event_address(&event.data, RegistryEvent::Deploy.actor_field()));
+
+    Ok(deployer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn signer(seed: u8) -> (SigningKey, String) {
+        let key = SigningKey::from_bytes(&[seed; 32]);
+        let owner = stellar_strkey::ed25519::PublicKey(key.verifying_key().to_bytes()).to_string();
+        (key, owner)
+    }
+
+    fn proof(challenges: &OwnerChallenges, key: &SigningKey, owner: &str) -> OwnerProof {
+        let challenge = new_challenge(challenges, owner.to_string());
+        OwnerProof {
+            owner: Some(owner.to_string()),
+            nonce: Some(challenge.nonce),
+            signature: Some(hex::encode(key.sign(challenge.challenge.as_bytes()).to_bytes())),
+        }
+    }
+
+    #[test]
+    fn signed_challenges_are_used_once() {
+        let challenges = OwnerChallenges::default();
+        let (key, owner) = signer(1);
+        let proof = proof(&challenges, &key, &owner);
+
+        assert!(verify_challenge(&challenges, &owner, &proof));
+        assert!(!verify_challenge(&challenges, &owner, &proof));
+    }
+
+    #[test]
+    fn wrong_signatures_keep_the_challenge() {
+        let challenges = OwnerChallenges::default();
+        let (key, owner) = signer(1);
+        let (other_key, _) = signer(2);
+        let valid = proof(&challenges, &key, &owner);
+        let forged = OwnerProof {
+            owner: valid.owner.clone(),
+            nonce: valid.nonce.clone(),
+            signature: Some(hex::encode(other_key.sign(b"SmartDeploy API").to_bytes())),
+        };
+
+        assert!(!verify_challenge(&challenges, &owner, &forged));
+        assert!(verify_challenge(&challenges, &owner, &valid));
+    }
+
+    #[test]
+    fn challenges_of_other_owners_are_refused() {
+        let challenges = OwnerChallenges::default();
+        let (key, owner) = signer(1);
+        let (_, other_owner) = signer(2);
+        let proof = proof(&challenges, &key, &owner);
+
+        assert!(!verify_challenge(&challenges, &other_owner, &proof));
+        // A new challenge for the same owner doesn't replace the pending one
+        new_challenge(&challenges, owner.clone());
+        assert!(verify_challenge(&challenges, &owner, &proof));
+    }
+
+    #[test]
+    fn expired_challenges_are_refused() {
+        let challenges = OwnerChallenges::default();
+        let (key, owner) = signer(1);
+        let proof = proof(&challenges, &key, &owner);
+        challenges.lock().unwrap().get_mut(proof.nonce.as_ref().unwrap()).unwrap().expires_at = now() - 1;
+
+        assert!(!verify_challenge(&challenges, &owner, &proof));
+    }
+
+    #[test]
+    fn pending_challenges_are_capped_per_owner() {
+        let challenges = OwnerChallenges::default();
+        let (key, owner) = signer(1);
+        let (other_key, other_owner) = signer(2);
+
+        let other_proof = proof(&challenges, &other_key, &other_owner);
+        let oldest = proof(&challenges, &key, &owner);
+        for _ in 0..MAX_PENDING_CHALLENGES_PER_OWNER {
+            new_challenge(&challenges, owner.clone());
+        }
+
+        assert_eq!(challenges.lock().unwrap().len(), MAX_PENDING_CHALLENGES_PER_OWNER + 1);
+        assert!(!verify_challenge(&challenges, &owner, &oldest));
+        assert!(verify_challenge(&challenges, &other_owner, &other_proof));
+    }
+
+    #[test]
+    fn expected_owner_must_sign() {
+        let challenges = OwnerChallenges::default();
+        let (key, owner) = signer(1);
+        let (other_key, other_owner) = signer(2);
+
+        let other_proof = proof(&challenges, &other_key, &other_owner);
+        let error = check_owner(&challenges, "C1", Some(owner.clone()), &other_proof, true).unwrap_err();
+        assert_eq!(error.0, StatusCode::FORBIDDEN);
+
+        let owner_proof = proof(&challenges, &key, &owner);
+        assert_eq!(check_owner(&challenges, "C1", Some(owner.clone()), &owner_proof, false).unwrap(), Some(owner));
+    }
+
+    #[test]
+    fn contract_owners_are_left_to_the_admin() {
+        let challenges = OwnerChallenges::default();
+        let (key, owner) = signer(1);
+        let contract_owner = stellar_strkey::Contract([3; 32]).to_string();
+        let proof = proof(&challenges, &key, &owner);
+
+        let error = check_owner(&challenges, "C1", Some(contract_owner.clone()), &proof, false).unwrap_err();
+        assert_eq!(error.0, StatusCode::FORBIDDEN);
+        assert!(error.1.contains("can't sign a challenge"));
+
+        assert_eq!(check_owner(&challenges, "C1", Some(contract_owner.clone()), &proof, true).unwrap(), Some(contract_owner));
+    }
+
+    #[test]
+    fn contracts_without_deployer_are_only_assigned_by_the_admin() {
+        let challenges = OwnerChallenges::default();
+        let (key, owner) = signer(1);
+        let signed = proof(&challenges, &key, &owner);
+
+        assert_eq!(check_owner(&challenges, "C1", None, &signed, false).unwrap_err().0, StatusCode::FORBIDDEN);
+        assert_eq!(check_owner(&challenges, "C1", None, &signed, true).unwrap(), Some(owner));
+
+        let unsigned = OwnerProof { owner: None, nonce: None, signature: None };
+        assert_eq!(check_owner(&challenges, "C1", None, &unsigned, true).unwrap(), None);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::AppState;
+use crate::network_profile::NetworkProfile;
+use crate::error::MyError;
+use crate::expiration::read_ledger::read_contract_ttl;
+use sqlx::FromRow;
+use std::sync::Arc;
+use tokio::time::{Duration, sleep};
+
+#[derive(FromRow)]
+struct StoredTtl {
+    contract_id: String,
+    live_until_ttl: i32,
+}
+
+// Function to read the real live until ledger of every contract of a network every 10 minutes,
+// and write it to contracts_ttl with the ledger it was read at
+pub async fn reconciler_cron_job(state: Arc<AppState>, network: Arc<NetworkProfile>) {
+
+    tokio::spawn(async move {
+
+        loop {
+
+            tracing::debug!("Reconciling contracts ttl of network {}", network.name);
+
+            match sqlx::query_as::<_, StoredTtl>("SELECT contract_id, live_until_ttl FROM contracts_ttl WHERE network = $1")
+                .bind(&network.name)
+                .fetch_all(&state.ttl_pool)
+                .await {
+
+                    Ok(stored_ttls) => {
+                        for stored_ttl in stored_ttls {
+                            if let Err(e) = reconcile_contract(&state, &network, &stored_ttl).await {
+                                tracing::error!("Error while reconciling ttl of {}: {}", stored_ttl.contract_id, e);
+                                if let Err(e) = flag_sync_error(&state, &network, &stored_ttl, e.to_string()).await {
+                                    tracing::error!("Error while flagging {}: {}", stored_ttl.contract_id, e);
+                                }
+                            }
+                        }
+                    },
+
+                    Err(e) => {
+                        tracing::error!("Error while retrieving contracts to reconcile: {:?}", e);
+                    }
+            };
+
+            sleep(Duration::from_secs(10 * 60)).await;
+        }
+    });
+}
+
+async fn reconcile_contract(state: &AppState, network: &NetworkProfile, stored_ttl: &StoredTtl) -> Result<(), MyError> {
+
+    let contract_ttl = read_contract_ttl(
+        stored_ttl.contract_id.clone(),
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    )
+    .await?;
+
+    // Archived contracts keep the live until ledger they expired at, and are flagged until restored
+    if contract_ttl.is_archived() {
+        return Err(MyError::ContractArchived(stored_ttl.contract_id.clone()));
+    }
+
+    let live_until_ttl = contract_ttl.instance_live_until_ledger as i32;
+    let out_of_sync = live_until_ttl != stored_ttl.live_until_ttl;
+    if out_of_sync {
+        tracing::debug!(
+            "Contract {} live until ledger was {}, {} on chain",
+            stored_ttl.contract_id,
+            stored_ttl.live_until_ttl,
+            live_until_ttl
+        );
+    }
+
+    sqlx::query("UPDATE contracts_ttl SET live_until_ttl = $1, checked_at_ledger = $2, out_of_sync = $3, sync_error = NULL WHERE network = $4 AND contract_id = $5")
+        .bind(live_until_ttl)
+        .bind(contract_ttl.latest_ledger as i32)
+        .bind(out_of_sync)
+        .bind(&network.name)
+        .bind(&stored_ttl.contract_id)
+        .execute(&state.ttl_pool)
+        .await?;
+
+    Ok(())
+}
+
+// The contract couldn't be checked: keep its live until ledger, flag it with the error
+async fn flag_sync_error(state: &AppState, network: &NetworkProfile, stored_ttl: &StoredTtl, error: String) -> Result<(), MyError> {
+
+    sqlx::query("UPDATE contracts_ttl SET out_of_sync = TRUE, sync_error = $1 WHERE network = $2 AND contract_id = $3")
+        .bind(error)
+        .bind(&network.name)
+        .bind(&stored_ttl.contract_id)
+        .execute(&state.ttl_pool)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use crate::AppState;
+use crate::admin::AdminAuth;
+use crate::error::MyError;
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const EXPIRATION_SUBSCRIPTION: &str = "expiration";
+pub const EVENT_SUBSCRIPTION: &str = "event";
+
+// Row of the mercury_subscriptions table
+#[derive(Serialize, FromRow)]
+pub struct Subscription {
+    pub id: i32,
+    pub network: String,
+    pub kind: String,
+    pub contract_id: String,
+    pub hash_xdr: Option<String>,
+    pub topics: Vec<String>,
+    pub mercury_response: String,
+    pub created_at: i64,
+}
+
+#[derive(Deserialize)]
+pub struct SubscriptionQuery {
+    pub contract_id: Option<String>,
+}
+
+// Record a subscription accepted by Mercury
+pub async fn record_subscription(
+    pool: &PgPool,
+    network: &NetworkProfile,
+    kind: &str,
+    contract_id: &str,
+    hash_xdr: Option<&str>,
+    topics: &[String],
+    mercury_response: &str,
+) -> Result<Subscription, MyError> {
+
+    let created_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+
+    let subscription = sqlx::query_as::<_, Subscription>("INSERT INTO mercury_subscriptions (network, kind, contract_id, hash_xdr, topics, mercury_response, created_at)
+                                        VALUES ($1, $2, $3, $4, $5, $6, $7)
+                                        RETURNING *;
+                                    ")
+                                    .bind(&network.name)
+                                    .bind(kind)
+                                    .bind(contract_id)
+                                    .bind(hash_xdr)
+                                    .bind(topics)
+                                    .bind(mercury_response)
+                                    .bind(created_at)
+                                    .fetch_one(pool)
+                                    .await?;
+
+    Ok(subscription)
+}
+
+// Handler to list the Mercury subscriptions of the network, optionally of one contract: ?contract_id=
+pub async fn list_subscriptions(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Query(query): Query<SubscriptionQuery>,
+) -> Result<Json<Vec<Subscription>>, MyError> {
+
+    let subscriptions = sqlx::query_as::<_, Subscription>("SELECT * FROM mercury_subscriptions
+                                        WHERE network = $1 AND ($2::VARCHAR IS NULL OR contract_id = $2)
+                                        ORDER BY created_at DESC;
+                                    ")
+                                    .bind(&network.name)
+                                    .bind(query.contract_id)
+                                    .fetch_all(&state.ttl_pool)
+                                    .await?;
+
+    Ok(Json(subscriptions))
+}
+
+// Handler to remove a subscription, returning the removed row
+// The subscription is only forgotten by the API, Mercury keeps indexing it until it's
+// removed on the Mercury side
+pub async fn unsubscribe(
+    _admin: AdminAuth,
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Path(id): Path<i32>,
+) -> Result<Json<Subscription>, (StatusCode, String)> {
+
+    let subscription = sqlx::query_as::<_, Subscription>("DELETE FROM mercury_subscriptions WHERE network = $1 AND id = $2 RETURNING *")
+        .bind(&network.name)
+        .bind(id)
+        .fetch_optional(&state.ttl_pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, MyError::SqlxError(e).to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Subscription {} not found", id)))?;
+
+    Ok(Json(subscription))
+}
@@ -0,0 +1,117 @@
+use crate::error::MyError;
+use ed25519_dalek::SigningKey;
+use soroban_cli::commands::config;
+use soroban_cli::rpc::Client;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+// Funded account signing the transactions sent by the service
+pub struct Signer {
+    pub key: SigningKey,
+    pub public_key: String,
+    // Last sequence number used, None until loaded from the RPC
+    sequence: Option<i64>,
+}
+
+// Pool of signers (the source account and the channel accounts) handing out one free signer
+// per transaction, so that concurrent transactions never race on the same sequence number
+pub struct SignerPool {
+    // Public keys of all the signers, in the order given to new()
+    public_keys: Vec<String>,
+    signers: Mutex<Vec<Signer>>,
+    available: Arc<Semaphore>,
+}
+
+impl SignerPool {
+    // Build the pool from secret keys, identities or seed phrases (same format as SOURCE_ACCOUNT)
+    pub fn new(accounts: &[String]) -> Result<Self, MyError> {
+
+        let signers = accounts
+            .iter()
+            .map(|account| {
+                let key = config::Args {
+                    source_account: account.clone(),
+                    ..Default::default()
+                }
+                .key_pair()
+                .map_err(MyError::ConfigNetworkError)?;
+                let public_key = stellar_strkey::ed25519::PublicKey(key.verifying_key().to_bytes()).to_string();
+                Ok(Signer { key, public_key, sequence: None })
+            })
+            .collect::<Result<Vec<_>, MyError>>()?;
+
+        Ok(SignerPool {
+            public_keys: signers.iter().map(|signer| signer.public_key.clone()).collect(),
+            available: Arc::new(Semaphore::new(signers.len())),
+            signers: Mutex::new(signers),
+        })
+    }
+
+    // Public keys of all the signers of the pool, the source account first
+    pub fn public_keys(&self) -> &[String] {
+        &self.public_keys
+    }
+
+    // Wait for a free signer. It's given back to the pool when the guard is dropped.
+    pub async fn acquire(self: &Arc<Self>) -> SignerGuard {
+
+        let permit = self.available
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("signer pool semaphore is never closed");
+
+        let signer = self.signers
+            .lock()
+            .unwrap()
+            .pop()
+            .expect("a permit guarantees a free signer");
+
+        SignerGuard {
+            pool: self.clone(),
+            signer: Some(signer),
+            _permit: permit,
+        }
+    }
+}
+
+// Exclusive use of a signer of the pool
+pub struct SignerGuard {
+    pool: Arc<SignerPool>,
+    signer: Option<Signer>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl SignerGuard {
+    pub fn signer(&self) -> &Signer {
+        self.signer.as_ref().unwrap()
+    }
+
+    // Sequence number of the next transaction, loaded from the RPC the first time
+    pub async fn next_sequence(&mut self, client: &Client) -> Result<i64, MyError> {
+
+        let signer = self.signer.as_mut().unwrap();
+
+        let sequence = match signer.sequence {
+            Some(sequence) => sequence,
+            None => client.get_account(&signer.public_key).await?.seq_num.into(),
+        };
+
+        signer.sequence = Some(sequence + 1);
+
+        Ok(sequence + 1)
+    }
+
+    // Forget the tracked sequence number (e.g. after a failed submission), it'll be reloaded
+    pub fn reset_sequence(&mut self) {
+        self.signer.as_mut().unwrap().sequence = None;
+    }
+}
+
+impl Drop for SignerGuard {
+    fn drop(&mut self) {
+        if let Some(signer) = self.signer.take() {
+            self.pool.signers.lock().unwrap().push(signer);
+        }
+    }
+}
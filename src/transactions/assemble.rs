@@ -0,0 +1,94 @@
+use crate::error::MyError;
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{
+    Limits,
+    OperationBody,
+    ReadXdr,
+    SorobanAuthorizationEntry,
+    Transaction,
+    TransactionEnvelope,
+    TransactionExt,
+    TransactionV1Envelope,
+    VecM,
+};
+use soroban_cli::fee;
+use soroban_cli::rpc::Client;
+
+// Simulate the transaction and apply the simulation result (footprint, resources, fees and
+// authorizations) to get a transaction ready to be signed, the same way the soroban CLI does
+pub async fn simulate_and_assemble(client: &Client, tx: Transaction) -> Result<Transaction, MyError> {
+
+    if tx.operations.len() != 1 {
+        return Err(MyError::UnsupportedTransaction("only single operation transactions can be simulated".to_string()));
+    }
+
+    let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+        tx: tx.clone(),
+        signatures: VecM::default(),
+    });
+
+    let simulation = client.simulate_transaction(&envelope).await?;
+
+    // Archived entries in the footprint must be restored first
+    if simulation.restore_preamble.is_some() {
+        return Err(MyError::RestoreRequired);
+    }
+
+    let transaction_data = simulation.transaction_data()?;
+
+    let mut tx = tx;
+    let mut op = tx.operations[0].clone();
+
+    // Authorizations required by the invoked contract
+    if let OperationBody::InvokeHostFunction(ref mut body) = op.body {
+        if body.auth.is_empty() {
+            if let Some(result) = simulation.results.first() {
+                let auth = result
+                    .auth
+                    .iter()
+                    .map(|auth| SorobanAuthorizationEntry::from_xdr_base64(auth, Limits::none()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(MyError::FromXdrError)?;
+                body.auth = auth.try_into().map_err(MyError::ToXdrError)?;
+            }
+        }
+    }
+
+    tx.fee = padded_fee(tx.fee, simulation.min_resource_fee);
+
+    tx.operations = vec![op].try_into().map_err(MyError::ToXdrError)?;
+    tx.ext = TransactionExt::V1(transaction_data);
+
+    Ok(tx)
+}
+
+// Pay at least the minimum resource fee, padded by 15% like the soroban CLI
+// Computed in u64 and capped to the largest fee a transaction can hold
+fn padded_fee(fee: u32, min_resource_fee: u64) -> u32 {
+
+    let fee = u64::from(fee).max(u64::from(fee::Args::default().fee).saturating_add(min_resource_fee));
+
+    u32::try_from(fee.saturating_mul(115) / 100).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padded_fee_adds_15_percent_to_the_resource_fee() {
+        let base_fee = u64::from(fee::Args::default().fee);
+        assert_eq!(u64::from(padded_fee(0, 10_000)), (base_fee + 10_000) * 115 / 100);
+    }
+
+    #[test]
+    fn padded_fee_keeps_a_higher_fee() {
+        assert_eq!(padded_fee(1_000_000, 10), 1_150_000);
+    }
+
+    #[test]
+    fn padded_fee_is_capped_instead_of_overflowing() {
+        assert_eq!(padded_fee(u32::MAX, 10), u32::MAX);
+        assert_eq!(padded_fee(100, u64::MAX), u32::MAX);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::error::MyError;
+use crate::network_profile::SelectedNetwork;
+use super::invoke::{
+    address_to_scval, build_host_function_transactions, bytes_to_scval, invoke_contract,
+    option_to_scval, string_to_scval, symbol_to_scval, xdr_to_scval, UnsignedTransaction,
+};
+use axum::Json;
+use serde::Deserialize;
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{Hash, ScMap, ScMapEntry, ScVal, ScVec};
+
+#[derive(Deserialize)]
+pub struct DeployRequest {
+    // Account of the caller, source of the transaction (G...)
+    pub source: String,
+    // Published contract to deploy
+    pub contract_name: String,
+    // Latest published version if not set
+    pub version: Option<Version>,
+    pub deployed_name: String,
+    // Account or contract owning the deployed contract
+    pub owner: String,
+    // Hex encoded 32 bytes salt of the contract address
+    pub salt: Option<String>,
+    // Function called right after the deployment
+    pub init: Option<InitCall>,
+}
+
+// Version of a published contract, as stored by the registry
+#[derive(Deserialize)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+#[derive(Deserialize)]
+pub struct InitCall {
+    pub function_name: String,
+    // Base64 XDR encoded ScVal arguments
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+// Handler to build the transaction deploying a published contract through the SmartDeploy registry
+// Returns the simulated, unsigned transaction to sign in the wallet
+pub async fn build_deploy_transaction(
+    SelectedNetwork(network): SelectedNetwork,
+    Json(request): Json<DeployRequest>,
+) -> Result<Json<UnsignedTransaction>, MyError> {
+
+    let Some(registry_id) = &network.smartdeploy_contract_id else {
+        return Err(MyError::RegistryNotConfigured(network.name.clone()));
+    };
+
+    let salt = match &request.salt {
+        Some(salt) => {
+            let salt = salt
+                .parse::<Hash>()
+                .map_err(|_| MyError::InvalidArgument(format!("salt {} is not 32 hex encoded bytes", salt)))?;
+            Some(bytes_to_scval(salt.0.to_vec())?)
+        },
+        None => None,
+    };
+
+    let init = match &request.init {
+        Some(init) => Some(init_to_scval(init)?),
+        None => None,
+    };
+
+    // deploy(contract_name, version, deployed_name, owner, salt, init)
+    let args = vec![
+        string_to_scval(&request.contract_name)?,
+        option_to_scval(request.version.as_ref().map(version_to_scval).transpose()?),
+        string_to_scval(&request.deployed_name)?,
+        address_to_scval(&request.owner)?,
+        option_to_scval(salt),
+        option_to_scval(init),
+    ];
+
+    let mut unsigned_transactions = build_host_function_transactions(
+        &network,
+        &request.source,
+        vec![invoke_contract(registry_id, "deploy", args)?]
+    )
+    .await?;
+
+    Ok(Json(unsigned_transactions.remove(0)))
+}
+
+// Contract structs are maps keyed by the field names, in alphabetical order
+fn version_to_scval(version: &Version) -> Result<ScVal, MyError> {
+
+    let fields = [
+        ("major", version.major),
+        ("minor", version.minor),
+        ("patch", version.patch),
+    ];
+
+    let entries = fields
+        .into_iter()
+        .map(|(name, value)| Ok(ScMapEntry {
+            key: ScVal::Symbol(symbol_to_scval(name)?),
+            val: ScVal::U32(value),
+        }))
+        .collect::<Result<Vec<_>, MyError>>()?;
+
+    Ok(ScVal::Map(Some(ScMap(entries.try_into().map_err(MyError::ToXdrError)?))))
+}
+
+// (Symbol, Vec<Val>) tuple
+fn init_to_scval(init: &InitCall) -> Result<ScVal, MyError> {
+
+    let args = init.args
+        .iter()
+        .map(|arg| xdr_to_scval(arg))
+        .collect::<Result<Vec<_>, MyError>>()?;
+
+    let tuple = vec![
+        ScVal::Symbol(symbol_to_scval(&init.function_name)?),
+        ScVal::Vec(Some(ScVec(args.try_into().map_err(MyError::ToXdrError)?))),
+    ];
+
+    Ok(ScVal::Vec(Some(ScVec(tuple.try_into().map_err(MyError::ToXdrError)?))))
+}
@@ -0,0 +1,105 @@
+use crate::error::MyError;
+use ed25519_dalek::{Signature, VerifyingKey};
+use sha2::{Sha256, Digest};
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{
+    DecoratedSignature,
+    Hash,
+    Limits,
+    MuxedAccount,
+    ReadXdr,
+    Transaction,
+    TransactionEnvelope,
+    TransactionSignaturePayload,
+    TransactionSignaturePayloadTaggedTransaction,
+    TransactionV1Envelope,
+    VecM,
+    WriteXdr,
+};
+
+// Decode a base64 transaction envelope XDR
+pub fn decode_envelope(envelope_xdr: &str) -> Result<TransactionEnvelope, MyError> {
+    TransactionEnvelope::from_xdr_base64(envelope_xdr, Limits::none())
+        .map_err(MyError::FromXdrError)
+}
+
+// Base64 XDR of the transaction wrapped into an envelope without signatures
+pub fn unsigned_envelope_xdr(tx: Transaction) -> Result<String, MyError> {
+    TransactionEnvelope::Tx(TransactionV1Envelope {
+        tx,
+        signatures: VecM::default(),
+    })
+    .to_xdr_base64(Limits::none())
+    .map_err(MyError::ToXdrError)
+}
+
+// Payload signed by the envelope signers
+fn signature_payload(envelope: &TransactionEnvelope) -> Result<TransactionSignaturePayloadTaggedTransaction, MyError> {
+    match envelope {
+        TransactionEnvelope::Tx(TransactionV1Envelope { tx, .. }) => {
+            Ok(TransactionSignaturePayloadTaggedTransaction::Tx(tx.clone()))
+        },
+        TransactionEnvelope::TxFeeBump(fee_bump) => {
+            Ok(TransactionSignaturePayloadTaggedTransaction::TxFeeBump(fee_bump.tx.clone()))
+        },
+        TransactionEnvelope::TxV0(_) => Err(MyError::UnsupportedTransaction("v0 transaction envelope".to_string())),
+    }
+}
+
+// Hash of the transaction (the one signed, and used to track it on the network)
+pub fn envelope_hash(envelope: &TransactionEnvelope, network_passphrase: &str) -> Result<[u8; 32], MyError> {
+
+    let payload = TransactionSignaturePayload {
+        network_id: Hash(Sha256::digest(network_passphrase).into()),
+        tagged_transaction: signature_payload(envelope)?,
+    };
+
+    let payload_xdr = payload
+        .to_xdr(Limits::none())
+        .map_err(MyError::ToXdrError)?;
+
+    Ok(Sha256::digest(payload_xdr).into())
+}
+
+// Hex hash of the transaction
+pub fn envelope_hash_hex(envelope: &TransactionEnvelope, network_passphrase: &str) -> Result<String, MyError> {
+    Ok(Hash(envelope_hash(envelope, network_passphrase)?).to_string())
+}
+
+// Ed25519 public key of the transaction source account
+pub fn source_account_key(tx: &Transaction) -> [u8; 32] {
+    match &tx.source_account {
+        MuxedAccount::Ed25519(key) => key.0,
+        MuxedAccount::MuxedEd25519(muxed) => muxed.ed25519.0,
+    }
+}
+
+// Check that the envelope carries a valid signature of the given account
+pub fn verify_signature(
+    envelope: &TransactionEnvelope,
+    signatures: &VecM<DecoratedSignature, 20>,
+    account: [u8; 32],
+    network_passphrase: &str,
+) -> Result<(), MyError> {
+
+    let hash = envelope_hash(envelope, network_passphrase)?;
+
+    let account_strkey = stellar_strkey::ed25519::PublicKey(account).to_string();
+    let verifying_key = VerifyingKey::from_bytes(&account)
+        .map_err(|_| MyError::InvalidSignature(account_strkey.clone()))?;
+
+    // The hint is the last 4 bytes of the signer public key
+    let is_signed = signatures
+        .iter()
+        .filter(|decorated| decorated.hint.0 == account[28..])
+        .any(|decorated| {
+            Signature::from_slice(&decorated.signature.0)
+                .is_ok_and(|signature| verifying_key.verify_strict(&hash, &signature).is_ok())
+        });
+
+    if !is_signed {
+        return Err(MyError::InvalidSignature(account_strkey));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use crate::error::MyError;
+use crate::network_profile::NetworkProfile;
+use super::assemble::simulate_and_assemble;
+use super::envelope::{envelope_hash_hex, unsigned_envelope_xdr};
+use serde::Serialize;
+use soroban_cli::fee;
+use soroban_cli::rpc::Client;
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{
+    AccountId, Hash, HostFunction, InvokeContractArgs, InvokeHostFunctionOp, Limits, Memo,
+    MuxedAccount, Operation, OperationBody, Preconditions, PublicKey, ReadXdr, ScAddress,
+    ScBytes, ScString, ScSymbol, ScVal, SequenceNumber, Transaction, TransactionEnvelope,
+    TransactionExt, TransactionV1Envelope, Uint256, VecM,
+};
+
+// Handler Response: transaction to sign in the wallet
+#[derive(Serialize)]
+pub struct UnsignedTransaction {
+    pub hash: String,
+    pub transaction_xdr: String,
+    pub network_passphrase: String,
+}
+
+impl UnsignedTransaction {
+    pub fn new(tx: Transaction, network_passphrase: &str) -> Result<Self, MyError> {
+
+        let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx: tx.clone(),
+            signatures: VecM::default(),
+        });
+
+        Ok(UnsignedTransaction {
+            hash: envelope_hash_hex(&envelope, network_passphrase)?,
+            transaction_xdr: unsigned_envelope_xdr(tx)?,
+            network_passphrase: network_passphrase.to_string(),
+        })
+    }
+}
+
+// Build and simulate the transactions of the source account (G...) running the host functions,
+// one transaction per host function, with consecutive sequence numbers
+pub async fn build_host_function_transactions(
+    network: &NetworkProfile,
+    source: &str,
+    host_functions: Vec<HostFunction>,
+) -> Result<Vec<UnsignedTransaction>, MyError> {
+
+    let source_account = stellar_strkey::ed25519::PublicKey::from_string(source)
+        .map_err(|e| MyError::StringToAccountConversionFailed(source.to_string(), e))?;
+
+    let client = Client::new(&network.rpc_url)?;
+    let account = client.get_account(source).await?;
+
+    let mut unsigned_transactions = Vec::new();
+    for (i, host_function) in host_functions.into_iter().enumerate() {
+
+        let tx = Transaction {
+            source_account: MuxedAccount::Ed25519(Uint256(source_account.0)),
+            fee: fee::Args::default().fee,
+            seq_num: SequenceNumber(account.seq_num.0 + 1 + i as i64),
+            cond: Preconditions::None,
+            memo: Memo::None,
+            operations: vec![Operation {
+                source_account: None,
+                body: OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+                    host_function,
+                    auth: VecM::default(),
+                }),
+            }]
+            .try_into()
+            .map_err(MyError::ToXdrError)?,
+            ext: TransactionExt::V0,
+        };
+
+        let tx = simulate_and_assemble(&client, tx).await?;
+        unsigned_transactions.push(UnsignedTransaction::new(tx, &network.network_passphrase)?);
+    }
+
+    Ok(unsigned_transactions)
+}
+
+// Host function invoking a contract function
+pub fn invoke_contract(
+    contract_id: &str,
+    function_name: &str,
+    args: Vec<ScVal>,
+) -> Result<HostFunction, MyError> {
+
+    let contract = stellar_strkey::Contract::from_string(contract_id)
+        .map_err(|e| MyError::StringToContractConversionFailed(contract_id.to_string(), e))?;
+
+    Ok(HostFunction::InvokeContract(InvokeContractArgs {
+        contract_address: ScAddress::Contract(Hash(contract.0)),
+        function_name: symbol_to_scval(function_name)?,
+        args: args.try_into().map_err(MyError::ToXdrError)?,
+    }))
+}
+
+pub fn symbol_to_scval(symbol: &str) -> Result<ScSymbol, MyError> {
+    Ok(ScSymbol(symbol.try_into().map_err(MyError::ToXdrError)?))
+}
+
+pub fn string_to_scval(string: &str) -> Result<ScVal, MyError> {
+    Ok(ScVal::String(ScString(string.try_into().map_err(MyError::ToXdrError)?)))
+}
+
+pub fn bytes_to_scval(bytes: Vec<u8>) -> Result<ScVal, MyError> {
+    Ok(ScVal::Bytes(ScBytes(bytes.try_into().map_err(MyError::ToXdrError)?)))
+}
+
+// Account (G...) or contract (C...) address
+pub fn address_to_scval(address: &str) -> Result<ScVal, MyError> {
+
+    if let Ok(account) = stellar_strkey::ed25519::PublicKey::from_string(address) {
+        return Ok(ScVal::Address(ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(account.0))))));
+    }
+
+    let contract = stellar_strkey::Contract::from_string(address)
+        .map_err(|e| MyError::StringToContractConversionFailed(address.to_string(), e))?;
+
+    Ok(ScVal::Address(ScAddress::Contract(Hash(contract.0))))
+}
+
+// Base64 XDR encoded value
+pub fn xdr_to_scval(xdr: &str) -> Result<ScVal, MyError> {
+    ScVal::from_xdr_base64(xdr, Limits::none()).map_err(MyError::FromXdrError)
+}
+
+// Option<T> contract argument
+pub fn option_to_scval(value: Option<ScVal>) -> ScVal {
+    value.unwrap_or(ScVal::Void)
+}
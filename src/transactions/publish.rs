@@ -0,0 +1,124 @@
+use crate::error::MyError;
+use crate::network_profile::SelectedNetwork;
+use super::invoke::{
+    address_to_scval, build_host_function_transactions, bytes_to_scval, invoke_contract,
+    option_to_scval, string_to_scval, symbol_to_scval, UnsignedTransaction,
+};
+use axum::extract::Multipart;
+use axum::Json;
+use serde::Serialize;
+use sha2::{Sha256, Digest};
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{Hash, HostFunction, ScVal, ScVec};
+
+// Handler Response: transactions to sign and submit in order
+#[derive(Serialize)]
+pub struct PublishTransactions {
+    // Hex sha256 hash of the wasm
+    pub wasm_hash: String,
+    pub upload_transaction: UnsignedTransaction,
+    pub publish_transaction: UnsignedTransaction,
+}
+
+// Handler to build the transactions uploading a wasm and publishing it to the SmartDeploy registry
+// Multipart fields: wasm (file), contract_name, author, optional source (author by default),
+// optional repo and optional kind (major, minor or patch: the registry bumps the version accordingly)
+pub async fn build_publish_transactions(
+    SelectedNetwork(network): SelectedNetwork,
+    mut multipart: Multipart,
+) -> Result<Json<PublishTransactions>, MyError> {
+
+    let Some(registry_id) = &network.smartdeploy_contract_id else {
+        return Err(MyError::RegistryNotConfigured(network.name.clone()));
+    };
+
+    let mut wasm = None;
+    let mut contract_name = None;
+    let mut author = None;
+    let mut source = None;
+    let mut repo = None;
+    let mut kind = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| MyError::InvalidArgument(e.body_text()))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "wasm" => wasm = Some(field.bytes().await.map_err(|e| MyError::InvalidArgument(e.body_text()))?.to_vec()),
+            _ => {
+                let value = field.text().await.map_err(|e| MyError::InvalidArgument(e.body_text()))?;
+                match name.as_str() {
+                    "contract_name" => contract_name = Some(value),
+                    "author" => author = Some(value),
+                    "source" => source = Some(value),
+                    "repo" => repo = Some(value),
+                    "kind" => kind = Some(value),
+                    _ => return Err(MyError::InvalidArgument(format!("unknown field {}", name))),
+                }
+            },
+        }
+    }
+
+    let wasm = wasm.ok_or_else(|| MyError::InvalidArgument("missing wasm file".to_string()))?;
+    let contract_name = contract_name.ok_or_else(|| MyError::InvalidArgument("missing contract_name".to_string()))?;
+    let author = author.ok_or_else(|| MyError::InvalidArgument("missing author".to_string()))?;
+    let source = source.unwrap_or_else(|| author.clone());
+
+    // Same hash as the one of the uploaded contract code
+    let wasm_hash = Hash(Sha256::digest(&wasm).into());
+
+    let kind = match kind.as_deref() {
+        Some(kind) => Some(update_kind_to_scval(kind)?),
+        None => None,
+    };
+    let repo = match &repo {
+        Some(repo) => Some(string_to_scval(repo)?),
+        None => None,
+    };
+
+    // publish(contract_name, author, hash, repo, kind)
+    let args = vec![
+        string_to_scval(&contract_name)?,
+        address_to_scval(&author)?,
+        bytes_to_scval(wasm_hash.0.to_vec())?,
+        option_to_scval(repo),
+        option_to_scval(kind),
+    ];
+
+    let upload_wasm = HostFunction::UploadContractWasm(
+        wasm.try_into().map_err(MyError::ToXdrError)?
+    );
+
+    let mut unsigned_transactions = build_host_function_transactions(
+        &network,
+        &source,
+        vec![upload_wasm, invoke_contract(registry_id, "publish", args)?]
+    )
+    .await?;
+
+    let publish_transaction = unsigned_transactions.remove(1);
+    let upload_transaction = unsigned_transactions.remove(0);
+
+    Ok(Json(PublishTransactions {
+        wasm_hash: wasm_hash.to_string(),
+        upload_transaction,
+        publish_transaction,
+    }))
+}
+
+// Contract enum unit variants are vectors holding the variant name
+fn update_kind_to_scval(kind: &str) -> Result<ScVal, MyError> {
+
+    let variant = match kind.to_lowercase().as_str() {
+        "major" => "Major",
+        "minor" => "Minor",
+        "patch" => "Patch",
+        _ => return Err(MyError::InvalidArgument(format!("kind {} is not major, minor or patch", kind))),
+    };
+
+    let variant = vec![ScVal::Symbol(symbol_to_scval(variant)?)];
+
+    Ok(ScVal::Vec(Some(ScVec(variant.try_into().map_err(MyError::ToXdrError)?))))
+}
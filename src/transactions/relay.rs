@@ -0,0 +1,104 @@
+use crate::AppState;
+use crate::error::MyError;
+use crate::network_profile::SelectedNetwork;
+use crate::expiration::read_ledger::read_contract_ttl;
+use crate::expiration::footprint::{build_footprint_transaction, FootprintOp};
+use super::assemble::simulate_and_assemble;
+use super::envelope::{decode_envelope, source_account_key, verify_signature};
+use super::invoke::UnsignedTransaction;
+use super::submit::{submit_transaction, track_transaction, SignedTransaction, TrackedTransaction};
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use serde::Deserialize;
+use soroban_cli::rpc::Client;
+use std::sync::Arc;
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{OperationBody, TransactionEnvelope, TransactionV1Envelope};
+
+pub const RELAY_BUMP_KIND: &str = "relay_bump";
+
+#[derive(Deserialize)]
+pub struct RelayBumpQuery {
+    // Account of the caller, paying for the transaction (G...)
+    pub source: String,
+}
+
+// Handler to build the transaction bumping a contract instance and its code, paid by the caller
+// Need the contract id, the number of ledgers to extend and the caller account as ?source=
+pub async fn build_relay_bump(
+    SelectedNetwork(network): SelectedNetwork,
+    Path((id, ledgers_to_extend)): Path<(String, u32)>,
+    Query(query): Query<RelayBumpQuery>,
+) -> Result<Json<UnsignedTransaction>, MyError> {
+
+    let source_account = stellar_strkey::ed25519::PublicKey::from_string(&query.source)
+        .map_err(|e| MyError::StringToAccountConversionFailed(query.source.clone(), e))?;
+
+    // Archived entries can't be extended, they must be restored first
+    let contract_ttl = read_contract_ttl(
+        id.clone(),
+        network.rpc_url.clone(),
+        network.network_passphrase.clone(),
+        network.source_account.clone()
+    )
+    .await?;
+
+    if contract_ttl.is_archived() {
+        return Err(MyError::ContractArchived(id));
+    }
+
+    let keys = contract_ttl.ledger_keys(&id)?;
+
+    let client = Client::new(&network.rpc_url)?;
+    let account = client.get_account(&query.source).await?;
+
+    let tx = build_footprint_transaction(
+        source_account.0,
+        account.seq_num.0 + 1,
+        keys,
+        FootprintOp::Extend(ledgers_to_extend)
+    )?;
+
+    let tx = simulate_and_assemble(&client, tx).await?;
+
+    Ok(Json(UnsignedTransaction::new(tx, &network.network_passphrase)?))
+}
+
+// Handler to submit the bump transaction signed by the caller
+// Only a single extend TTL operation signed by the transaction source account is relayed
+pub async fn submit_relay_bump(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Json(signed): Json<SignedTransaction>,
+) -> Result<Json<TrackedTransaction>, MyError> {
+
+    let envelope = decode_envelope(&signed.transaction_xdr)?;
+
+    let TransactionEnvelope::Tx(TransactionV1Envelope { tx, signatures }) = &envelope else {
+        return Err(MyError::UnsupportedTransaction("expected a v1 transaction envelope".to_string()));
+    };
+
+    let is_single_extend = tx.operations.len() == 1
+        && matches!(tx.operations[0].body, OperationBody::ExtendFootprintTtl(_))
+        && tx.operations[0].source_account.is_none();
+    if !is_single_extend {
+        return Err(MyError::UnsupportedTransaction("expected a single extend TTL operation".to_string()));
+    }
+
+    let source_key = source_account_key(tx);
+    verify_signature(&envelope, signatures, source_key, &network.network_passphrase)?;
+
+    let source_account = stellar_strkey::ed25519::PublicKey(source_key).to_string();
+    let transaction = submit_transaction(
+        &state.ttl_pool,
+        &network,
+        &envelope,
+        RELAY_BUMP_KIND,
+        &source_account
+    )
+    .await?;
+
+    track_transaction(state.ttl_pool.clone(), network.clone(), transaction.hash.clone());
+
+    Ok(Json(transaction))
+}
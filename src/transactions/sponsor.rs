@@ -0,0 +1,481 @@
+use crate::AppState;
+use crate::admin::AdminAuth;
+use crate::error::MyError;
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
+use crate::balance_monitor::check_manual_bump_funds;
+use super::envelope::{decode_envelope, envelope_hash, source_account_key, verify_signature};
+use super::submit::{send_transaction, track_transaction, TrackedTransaction, STATUS_PENDING};
+use crate::events::event_data::address_to_strkey;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use ed25519_dalek::Signer as _;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{
+    DecoratedSignature, FeeBumpTransaction, FeeBumpTransactionEnvelope, FeeBumpTransactionExt,
+    FeeBumpTransactionInnerTx, HostFunction, LedgerKey, LedgerKeyContractCode, LedgerKeyContractData,
+    MuxedAccount, OperationBody, SignatureHint, Transaction, TransactionEnvelope, TransactionExt,
+    TransactionV1Envelope, Uint256,
+};
+
+pub const SPONSORED_KIND: &str = "sponsored";
+
+// Minimum inclusion fee per operation (stroops)
+const BASE_FEE: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct SponsorRequest {
+    // Transaction signed by the user, wrapped as is
+    pub transaction_xdr: String,
+}
+
+// Row of the sponsor_quotas table
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct SponsorQuota {
+    // Set from the selected network, ignored in the request body
+    #[serde(default)]
+    pub network: String,
+    pub account: String,
+    pub max_transactions: i32,
+    // In stroops
+    pub max_fee: i64,
+    #[serde(default)]
+    pub used_transactions: i32,
+    #[serde(default)]
+    pub used_fee: i64,
+}
+
+// Handler to submit a user-signed Soroban transaction, the fees being paid by the service
+// The transaction is wrapped in a fee-bump envelope as long as the user is within its quota
+pub async fn submit_sponsored_transaction(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Json(request): Json<SponsorRequest>,
+) -> Result<Json<TrackedTransaction>, MyError> {
+
+    check_manual_bump_funds(&network)?;
+
+    let envelope = decode_envelope(&request.transaction_xdr)?;
+
+    let TransactionEnvelope::Tx(TransactionV1Envelope { tx, signatures }) = &envelope else {
+        return Err(MyError::UnsupportedTransaction("expected a v1 transaction envelope".to_string()));
+    };
+
+    let Some(registry_id) = &network.smartdeploy_contract_id else {
+        return Err(MyError::RegistryNotConfigured(network.name.clone()));
+    };
+
+    // Only SmartDeploy interactions are sponsored: invocations of the registry,
+    // and TTL operations on the contracts deployed (and the wasm published) through it
+    match sponsored_scope(tx, registry_id)? {
+        SponsoredScope::Registry => (),
+        SponsoredScope::Footprint { contract_ids, wasm_hashes } => {
+            check_registry_footprint(&state.ttl_pool, &network, registry_id, &contract_ids, &wasm_hashes).await?;
+        },
+    }
+
+    let source_key = source_account_key(tx);
+    verify_signature(&envelope, signatures, source_key, &network.network_passphrase)?;
+    let source_account = stellar_strkey::ed25519::PublicKey(source_key).to_string();
+
+    // The quota is reserved first so concurrent requests can't exceed it,
+    // and given back if the transaction isn't accepted by the network
+    let fee = fee_bump_fee(tx);
+    consume_quota(&state.ttl_pool, &network, &source_account, fee).await?;
+
+    let submitted = match fee_bump(&network, envelope.clone(), fee).await {
+        Ok(fee_bump_envelope) => send_transaction(
+            &state.ttl_pool,
+            &network,
+            &fee_bump_envelope,
+            SPONSORED_KIND,
+            &source_account
+        ).await,
+        Err(e) => Err(e),
+    };
+
+    // Transactions already submitted are only charged once
+    let accepted = matches!(&submitted, Ok((transaction, true)) if transaction.status == STATUS_PENDING);
+    if !accepted {
+        refund_quota(&state.ttl_pool, &network, &source_account, fee).await?;
+    }
+
+    let (transaction, _) = submitted?;
+
+    track_transaction(state.ttl_pool.clone(), network.clone(), transaction.hash.clone());
+
+    Ok(Json(transaction))
+}
+
+// What a sponsored transaction is allowed to touch
+#[derive(Debug, PartialEq)]
+enum SponsoredScope {
+    // Invocation of the registry contract
+    Registry,
+    // TTL operation on these contracts (C...) and contract codes (hex wasm hashes)
+    Footprint {
+        contract_ids: Vec<String>,
+        wasm_hashes: Vec<String>,
+    },
+}
+
+// Scope of a transaction holding a single Soroban operation, refused if it's not a SmartDeploy interaction
+fn sponsored_scope(tx: &Transaction, registry_id: &str) -> Result<SponsoredScope, MyError> {
+
+    if tx.operations.len() != 1 {
+        return Err(MyError::UnsupportedTransaction("expected a single Soroban operation".to_string()));
+    }
+
+    match &tx.operations[0].body {
+        OperationBody::InvokeHostFunction(op) => match &op.host_function {
+            HostFunction::InvokeContract(args) if address_to_strkey(&args.contract_address) == registry_id => {
+                Ok(SponsoredScope::Registry)
+            },
+            _ => Err(MyError::UnsupportedTransaction(format!("only the invocations of the registry {} are sponsored", registry_id))),
+        },
+        OperationBody::ExtendFootprintTtl(_) | OperationBody::RestoreFootprint(_) => {
+
+            let TransactionExt::V1(soroban_data) = &tx.ext else {
+                return Err(MyError::UnsupportedTransaction("missing Soroban transaction data".to_string()));
+            };
+
+            let footprint = &soroban_data.resources.footprint;
+            let mut contract_ids = Vec::new();
+            let mut wasm_hashes = Vec::new();
+
+            for key in footprint.read_only.iter().chain(footprint.read_write.iter()) {
+                match key {
+                    LedgerKey::ContractData(LedgerKeyContractData { contract, .. }) => contract_ids.push(address_to_strkey(contract)),
+                    LedgerKey::ContractCode(LedgerKeyContractCode { hash }) => wasm_hashes.push(hash.to_string()),
+                    _ => return Err(MyError::UnsupportedTransaction("only contract data and code entries can be extended or restored".to_string())),
+                }
+            }
+
+            Ok(SponsoredScope::Footprint { contract_ids, wasm_hashes })
+        },
+        _ => Err(MyError::UnsupportedTransaction("expected a single Soroban operation".to_string())),
+    }
+}
+
+// Every contract of the footprint must be the registry or deployed through it,
+// every contract code a wasm published to it
+async fn check_registry_footprint(
+    pool: &PgPool,
+    network: &NetworkProfile,
+    registry_id: &str,
+    contract_ids: &[String],
+    wasm_hashes: &[String],
+) -> Result<(), MyError> {
+
+    let contract_ids = contract_ids
+        .iter()
+        .filter(|contract_id| *contract_id != registry_id)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let deployed = sqlx::query_scalar::<_, i64>("SELECT COUNT(DISTINCT contract_id) FROM registry_events
+                                        WHERE network = $1 AND kind = 'deploy' AND contract_id = ANY($2)
+                                    ")
+                                    .bind(&network.name)
+                                    .bind(&contract_ids)
+                                    .fetch_one(pool)
+                                    .await?;
+
+    let published = sqlx::query_scalar::<_, i64>("SELECT COUNT(DISTINCT wasm_hash) FROM registry_events
+                                        WHERE network = $1 AND kind = 'publish' AND wasm_hash = ANY($2)
+                                    ")
+                                    .bind(&network.name)
+                                    .bind(wasm_hashes)
+                                    .fetch_one(pool)
+                                    .await?;
+
+    let distinct = |items: &[String]| items.iter().collect::<std::collections::HashSet<_>>().len() as i64;
+
+    if deployed != distinct(&contract_ids) || published != distinct(wasm_hashes) {
+        return Err(MyError::UnsupportedTransaction("only the contracts deployed through the registry are sponsored".to_string()));
+    }
+
+    Ok(())
+}
+
+// Fee of the fee-bump envelope: the inner resource fee plus the inner inclusion fee rate
+// for the inner operations and the fee-bump itself
+fn fee_bump_fee(tx: &Transaction) -> i64 {
+
+    let resource_fee = match &tx.ext {
+        TransactionExt::V1(data) => data.resource_fee,
+        TransactionExt::V0 => 0,
+    };
+
+    let operations = tx.operations.len() as i64;
+    let inclusion_fee_rate = ((tx.fee as i64 - resource_fee) / operations).max(BASE_FEE);
+
+    resource_fee + inclusion_fee_rate * (operations + 1)
+}
+
+// Wrap the inner transaction in a fee-bump envelope signed by a signer of the pool
+async fn fee_bump(
+    network: &NetworkProfile,
+    inner: TransactionEnvelope,
+    fee: i64,
+) -> Result<TransactionEnvelope, MyError> {
+
+    let TransactionEnvelope::Tx(inner) = inner else {
+        return Err(MyError::UnsupportedTransaction("expected a v1 transaction envelope".to_string()));
+    };
+
+    // The fee source doesn't use its sequence number
+    let signer_guard = network.signer_pool.acquire().await;
+    let signer = signer_guard.signer();
+    let fee_source = signer.key.verifying_key().to_bytes();
+
+    let mut envelope = TransactionEnvelope::TxFeeBump(FeeBumpTransactionEnvelope {
+        tx: FeeBumpTransaction {
+            fee_source: MuxedAccount::Ed25519(Uint256(fee_source)),
+            fee,
+            inner_tx: FeeBumpTransactionInnerTx::Tx(inner),
+            ext: FeeBumpTransactionExt::V0,
+        },
+        signatures: Default::default(),
+    });
+
+    let hash = envelope_hash(&envelope, &network.network_passphrase)?;
+    let signature = DecoratedSignature {
+        hint: SignatureHint(fee_source[28..].try_into().unwrap()),
+        signature: signer.key.sign(&hash).to_bytes().to_vec().try_into().map_err(MyError::ToXdrError)?,
+    };
+
+    if let TransactionEnvelope::TxFeeBump(fee_bump) = &mut envelope {
+        fee_bump.signatures = vec![signature].try_into().map_err(MyError::ToXdrError)?;
+    }
+
+    Ok(envelope)
+}
+
+// Count the transaction and its fee in the user quota, refused if it would exceed it
+async fn consume_quota(
+    pool: &PgPool,
+    network: &NetworkProfile,
+    account: &str,
+    fee: i64,
+) -> Result<(), MyError> {
+
+    // First sponsored transaction of the user: start with the network default quota
+    sqlx::query("INSERT INTO sponsor_quotas (network, account, max_transactions, max_fee)
+                    VALUES ($1, $2, $3, $4)
+                    ON CONFLICT (network, account) DO NOTHING;
+                ")
+                .bind(&network.name)
+                .bind(account)
+                .bind(network.sponsor_max_transactions)
+                .bind(network.sponsor_max_fee)
+                .execute(pool)
+                .await?;
+
+    let consumed = sqlx::query("UPDATE sponsor_quotas
+                    SET used_transactions = used_transactions + 1, used_fee = used_fee + $3
+                    WHERE network = $1 AND account = $2
+                    AND used_transactions < max_transactions AND used_fee + $3 <= max_fee;
+                ")
+                .bind(&network.name)
+                .bind(account)
+                .bind(fee)
+                .execute(pool)
+                .await?;
+
+    if consumed.rows_affected() == 0 {
+        return Err(MyError::SponsorQuotaExceeded(account.to_string()));
+    }
+
+    Ok(())
+}
+
+// Give back the quota reserved for a transaction the network didn't accept
+async fn refund_quota(
+    pool: &PgPool,
+    network: &NetworkProfile,
+    account: &str,
+    fee: i64,
+) -> Result<(), MyError> {
+
+    sqlx::query("UPDATE sponsor_quotas
+                    SET used_transactions = GREATEST(used_transactions - 1, 0), used_fee = GREATEST(used_fee - $3, 0)
+                    WHERE network = $1 AND account = $2;
+                ")
+                .bind(&network.name)
+                .bind(account)
+                .bind(fee)
+                .execute(pool)
+                .await?;
+
+    Ok(())
+}
+
+// Handler to read the sponsorship quota of a user account
+pub async fn get_sponsor_quota(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Path(account): Path<String>,
+) -> Result<Json<SponsorQuota>, MyError> {
+
+    let quota = sqlx::query_as::<_, SponsorQuota>("SELECT * FROM sponsor_quotas WHERE network = $1 AND account = $2")
+        .bind(&network.name)
+        .bind(&account)
+        .fetch_optional(&state.ttl_pool)
+        .await?
+        .unwrap_or(SponsorQuota {
+            network: network.name.clone(),
+            account,
+            max_transactions: network.sponsor_max_transactions,
+            max_fee: network.sponsor_max_fee,
+            used_transactions: 0,
+            used_fee: 0,
+        });
+
+    Ok(Json(quota))
+}
+
+// Admin handler to set the sponsorship quota of a user account, the usage is kept
+pub async fn set_sponsor_quota(
+    _admin: AdminAuth,
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Json(quota): Json<SponsorQuota>,
+) -> Result<(StatusCode, Json<SponsorQuota>), MyError> {
+
+    let quota = sqlx::query_as::<_, SponsorQuota>("INSERT INTO sponsor_quotas (network, account, max_transactions, max_fee)
+                                        VALUES ($1, $2, $3, $4)
+                                        ON CONFLICT (network, account) DO UPDATE
+                                        SET max_transactions = $3, max_fee = $4
+                                        RETURNING *;
+                                    ")
+                                    .bind(&network.name)
+                                    .bind(quota.account)
+                                    .bind(quota.max_transactions)
+                                    .bind(quota.max_fee)
+                                    .fetch_one(&state.ttl_pool)
+                                    .await?;
+
+    Ok((StatusCode::CREATED, Json(quota)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::stellar_xdr::curr::{
+        ContractDataDurability, ExtendFootprintTtlOp, ExtensionPoint, Hash, InvokeContractArgs,
+        InvokeHostFunctionOp, LedgerFootprint, Memo, Operation, Preconditions, ScAddress, ScSymbol,
+        ScVal, SequenceNumber, SorobanResources, SorobanTransactionData, VecM,
+    };
+
+    const REGISTRY: [u8; 32] = [7; 32];
+
+    fn transaction(body: OperationBody, fee: u32, ext: TransactionExt) -> Transaction {
+        Transaction {
+            source_account: MuxedAccount::Ed25519(Uint256([0; 32])),
+            fee,
+            seq_num: SequenceNumber(1),
+            cond: Preconditions::None,
+            memo: Memo::None,
+            operations: vec![Operation { source_account: None, body }].try_into().unwrap(),
+            ext,
+        }
+    }
+
+    fn soroban_data(resource_fee: i64, read_only: Vec<LedgerKey>) -> TransactionExt {
+        TransactionExt::V1(SorobanTransactionData {
+            ext: ExtensionPoint::V0,
+            resources: SorobanResources {
+                footprint: LedgerFootprint {
+                    read_only: read_only.try_into().unwrap(),
+                    read_write: VecM::default(),
+                },
+                instructions: 0,
+                read_bytes: 0,
+                write_bytes: 0,
+            },
+            resource_fee,
+        })
+    }
+
+    fn invoke(contract: [u8; 32]) -> OperationBody {
+        OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+            host_function: HostFunction::InvokeContract(InvokeContractArgs {
+                contract_address: ScAddress::Contract(Hash(contract)),
+                function_name: ScSymbol("deploy".try_into().unwrap()),
+                args: VecM::default(),
+            }),
+            auth: VecM::default(),
+        })
+    }
+
+    fn extend() -> OperationBody {
+        OperationBody::ExtendFootprintTtl(ExtendFootprintTtlOp { ext: ExtensionPoint::V0, extend_to: 100 })
+    }
+
+    fn registry_id() -> String {
+        stellar_strkey::Contract(REGISTRY).to_string()
+    }
+
+    #[test]
+    fn fee_bump_fee_adds_an_inclusion_fee_for_the_fee_bump() {
+        let tx = transaction(extend(), 10_300, soroban_data(10_000, vec![]));
+        // 300 stroops of inclusion fee for 1 operation, paid again for the fee-bump
+        assert_eq!(fee_bump_fee(&tx), 10_000 + 300 * 2);
+    }
+
+    #[test]
+    fn fee_bump_fee_pays_at_least_the_base_fee() {
+        let tx = transaction(extend(), 10_000, soroban_data(10_000, vec![]));
+        assert_eq!(fee_bump_fee(&tx), 10_000 + BASE_FEE * 2);
+    }
+
+    #[test]
+    fn registry_invocations_are_sponsored() {
+        let tx = transaction(invoke(REGISTRY), 100, TransactionExt::V0);
+        assert_eq!(sponsored_scope(&tx, &registry_id()).ok(), Some(SponsoredScope::Registry));
+    }
+
+    #[test]
+    fn other_invocations_are_not_sponsored() {
+        let tx = transaction(invoke([8; 32]), 100, TransactionExt::V0);
+        assert!(sponsored_scope(&tx, &registry_id()).is_err());
+
+        let upload = OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+            host_function: HostFunction::UploadContractWasm(vec![0u8; 8].try_into().unwrap()),
+            auth: VecM::default(),
+        });
+        let tx = transaction(upload, 100, TransactionExt::V0);
+        assert!(sponsored_scope(&tx, &registry_id()).is_err());
+    }
+
+    #[test]
+    fn ttl_operations_are_scoped_to_their_footprint() {
+        let keys = vec![
+            LedgerKey::ContractData(LedgerKeyContractData {
+                contract: ScAddress::Contract(Hash([9; 32])),
+                key: ScVal::LedgerKeyContractInstance,
+                durability: ContractDataDurability::Persistent,
+            }),
+            LedgerKey::ContractCode(LedgerKeyContractCode { hash: Hash([0xab; 32]) }),
+        ];
+        let tx = transaction(extend(), 100, soroban_data(0, keys));
+
+        assert_eq!(
+            sponsored_scope(&tx, &registry_id()).ok(),
+            Some(SponsoredScope::Footprint {
+                contract_ids: vec![stellar_strkey::Contract([9; 32]).to_string()],
+                wasm_hashes: vec!["ab".repeat(32)],
+            })
+        );
+    }
+
+    #[test]
+    fn ttl_operations_without_soroban_data_are_not_sponsored() {
+        let tx = transaction(extend(), 100, TransactionExt::V0);
+        assert!(sponsored_scope(&tx, &registry_id()).is_err());
+    }
+}
@@ -0,0 +1,362 @@
+use crate::AppState;
+use crate::error::MyError;
+use crate::network_profile::{NetworkProfile, SelectedNetwork};
+use crate::transactions::envelope::{decode_envelope, envelope_hash_hex};
+use axum::{extract::{Path, State}, Json};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::json;
+use sqlx::{FromRow, PgPool};
+use sqlx::types::Json as JsonColumn;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{
+    Limits, MuxedAccount, ReadXdr, TransactionEnvelope,
+    TransactionResult, TransactionResultResult, WriteXdr,
+};
+use tokio::time::{Duration, sleep};
+
+// Statuses of a tracked transaction
+pub const STATUS_PENDING: &str = "PENDING";
+pub const STATUS_SUCCESS: &str = "SUCCESS";
+pub const STATUS_FAILED: &str = "FAILED";
+// Not accepted by the congested network, submit it again later
+pub const STATUS_TRY_AGAIN_LATER: &str = "TRY_AGAIN_LATER";
+
+// Kind of the transactions sent to /tx/submit
+pub const SUBMITTED_KIND: &str = "submitted";
+
+// Poll the RPC every 2 seconds for 2 minutes at most
+const POLL_INTERVAL_SECS: u64 = 2;
+const POLL_ATTEMPTS: u32 = 60;
+
+// Row of the transactions table
+#[derive(Serialize, FromRow, Clone)]
+pub struct TrackedTransaction {
+    pub hash: String,
+    pub network: String,
+    pub kind: String,
+    pub source_account: String,
+    pub status: String,
+    pub envelope_xdr: String,
+    pub ledger: Option<i32>,
+    pub result_xdr: Option<String>,
+    // Decoded result_xdr
+    pub result: Option<JsonColumn<serde_json::Value>>,
+    // Fee (in stroops) charged by the network
+    pub fee_charged: Option<i64>,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Deserialize)]
+pub struct SignedTransaction {
+    pub transaction_xdr: String,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SendTransactionResult {
+    status: String,
+    error_result_xdr: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetTransactionResult {
+    status: String,
+    ledger: Option<u32>,
+    result_xdr: Option<String>,
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+async fn rpc_call<T: DeserializeOwned>(
+    rpc_url: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<T, MyError> {
+
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+
+    let res = reqwest::Client::new()
+        .post(rpc_url)
+        .json(&body)
+        .send()
+        .await?;
+
+    if !res.status().is_success() {
+        return Err(MyError::BadResponseStatus);
+    }
+
+    let response = res.json::<JsonRpcResponse<T>>().await?;
+    match (response.result, response.error) {
+        (Some(result), _) => Ok(result),
+        (None, Some(error)) => Err(MyError::JsonRpcError(error.message)),
+        (None, None) => Err(MyError::EmptyData),
+    }
+}
+
+// Record the signed transaction as PENDING and send it to the network
+// The row is returned as is if the same transaction has already been submitted,
+// unless it never made it to a ledger: it's sent again
+pub async fn submit_transaction(
+    pool: &PgPool,
+    network: &NetworkProfile,
+    envelope: &TransactionEnvelope,
+    kind: &str,
+    source_account: &str,
+) -> Result<TrackedTransaction, MyError> {
+
+    let (transaction, _) = send_transaction(pool, network, envelope, kind, source_account).await?;
+
+    Ok(transaction)
+}
+
+// Same as submit_transaction, also telling whether the transaction was sent to the network by this call
+pub async fn send_transaction(
+    pool: &PgPool,
+    network: &NetworkProfile,
+    envelope: &TransactionEnvelope,
+    kind: &str,
+    source_account: &str,
+) -> Result<(TrackedTransaction, bool), MyError> {
+
+    let hash = envelope_hash_hex(envelope, &network.network_passphrase)?;
+    let envelope_xdr = envelope
+        .to_xdr_base64(Limits::none())
+        .map_err(MyError::ToXdrError)?;
+
+    let inserted = sqlx::query_as::<_, TrackedTransaction>("INSERT INTO transactions (hash, network, kind, source_account, status, envelope_xdr, created_at, updated_at)
+                                        VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
+                                        ON CONFLICT (hash) DO UPDATE
+                                        SET status = $5, ledger = NULL, result_xdr = NULL, result = NULL, fee_charged = NULL, error = NULL, updated_at = $7
+                                        WHERE transactions.status = $8 OR (transactions.status = $9 AND transactions.ledger IS NULL)
+                                        RETURNING *;
+                                    ")
+                                    .bind(&hash)
+                                    .bind(&network.name)
+                                    .bind(kind)
+                                    .bind(source_account)
+                                    .bind(STATUS_PENDING)
+                                    .bind(&envelope_xdr)
+                                    .bind(now())
+                                    .bind(STATUS_TRY_AGAIN_LATER)
+                                    .bind(STATUS_FAILED)
+                                    .fetch_optional(pool)
+                                    .await?;
+
+    let Some(transaction) = inserted else {
+        return Ok((get_tracked_transaction(pool, &network.name, &hash).await?, false));
+    };
+
+    tracing::debug!("Submitting {} transaction {}", kind, hash);
+
+    let sent = rpc_call::<SendTransactionResult>(
+        &network.rpc_url,
+        "sendTransaction",
+        json!({ "transaction": envelope_xdr }),
+    ).await;
+
+    let transaction = match sent {
+        Ok(sent) if sent.status == "ERROR" => {
+            update_status(pool, &hash, STATUS_FAILED, None, sent.error_result_xdr, Some("rejected by the network".to_string())).await?
+        },
+        Ok(sent) if sent.status == "TRY_AGAIN_LATER" => {
+            update_status(pool, &hash, STATUS_TRY_AGAIN_LATER, None, None, Some("network congested, try again later".to_string())).await?
+        },
+        Ok(_) => transaction,
+        Err(e) => update_status(pool, &hash, STATUS_FAILED, None, None, Some(e.to_string())).await?,
+    };
+
+    Ok((transaction, true))
+}
+
+// Poll the network in the background until the transaction is final
+pub fn track_transaction(pool: PgPool, network: Arc<NetworkProfile>, hash: String) {
+
+    tokio::spawn(async move {
+        if let Err(e) = poll_transaction(&pool, &network, &hash).await {
+            tracing::error!("Error while tracking transaction {}: {}", hash, e);
+        }
+    });
+}
+
+// Wait for the transaction to be included (or rejected) and record the outcome
+pub async fn poll_transaction(
+    pool: &PgPool,
+    network: &NetworkProfile,
+    hash: &str,
+) -> Result<TrackedTransaction, MyError> {
+
+    let transaction = get_tracked_transaction(pool, &network.name, hash).await?;
+    if transaction.status != STATUS_PENDING {
+        return Ok(transaction);
+    }
+
+    for _ in 0..POLL_ATTEMPTS {
+
+        sleep(Duration::from_secs(POLL_INTERVAL_SECS)).await;
+
+        let result = match rpc_call::<GetTransactionResult>(
+            &network.rpc_url,
+            "getTransaction",
+            json!({ "hash": hash }),
+        ).await {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::error!("Error while polling transaction {}: {}", hash, e);
+                continue;
+            }
+        };
+
+        let ledger = result.ledger.map(|ledger| ledger as i32);
+        match result.status.as_str() {
+            "SUCCESS" => return update_status(pool, hash, STATUS_SUCCESS, ledger, result.result_xdr, None).await,
+            "FAILED" => return update_status(pool, hash, STATUS_FAILED, ledger, result.result_xdr, Some("transaction failed".to_string())).await,
+            _ => continue,
+        }
+    }
+
+    update_status(pool, hash, STATUS_FAILED, None, None, Some("transaction not included in time".to_string())).await
+}
+
+// Record the outcome, the result code of a failed transaction replacing the generic error
+async fn update_status(
+    pool: &PgPool,
+    hash: &str,
+    status: &str,
+    ledger: Option<i32>,
+    result_xdr: Option<String>,
+    error: Option<String>,
+) -> Result<TrackedTransaction, MyError> {
+
+    let result = result_xdr
+        .as_deref()
+        .and_then(|result_xdr| TransactionResult::from_xdr_base64(result_xdr, Limits::none()).ok());
+
+    let error = match (&result, error) {
+        (Some(result), Some(_)) => Some(result_error(result)),
+        (_, error) => error,
+    };
+
+    let fee_charged = result.as_ref().map(|result| result.fee_charged);
+
+    let result = result
+        .map(|result| serde_json::to_value(result).map(JsonColumn))
+        .transpose()
+        .map_err(|e| MyError::InvalidArgument(e.to_string()))?;
+
+    let transaction = sqlx::query_as::<_, TrackedTransaction>("UPDATE transactions
+                                        SET status = $2, ledger = $3, result_xdr = $4, result = $5, fee_charged = $6, error = $7, updated_at = $8
+                                        WHERE hash = $1
+                                        RETURNING *;
+                                    ")
+                                    .bind(hash)
+                                    .bind(status)
+                                    .bind(ledger)
+                                    .bind(result_xdr)
+                                    .bind(result)
+                                    .bind(fee_charged)
+                                    .bind(error)
+                                    .bind(now())
+                                    .fetch_one(pool)
+                                    .await?;
+
+    Ok(transaction)
+}
+
+// Result code of the transaction, e.g. TxBadSeq, with the failed operation results
+fn result_error(result: &TransactionResult) -> String {
+    match &result.result {
+        TransactionResultResult::TxFailed(operations) => format!("TxFailed: {:?}", operations.as_slice()),
+        TransactionResultResult::TxFeeBumpInnerFailed(inner) => format!("TxFeeBumpInnerFailed: {}", inner.result.result.name()),
+        result => result.name().to_string(),
+    }
+}
+
+// Account paying for the transaction: the fee source of a fee-bump, the source otherwise
+pub fn fee_source_account(envelope: &TransactionEnvelope) -> String {
+
+    let account = match envelope {
+        TransactionEnvelope::TxV0(envelope) => return stellar_strkey::ed25519::PublicKey(envelope.tx.source_account_ed25519.0).to_string(),
+        TransactionEnvelope::Tx(envelope) => &envelope.tx.source_account,
+        TransactionEnvelope::TxFeeBump(envelope) => &envelope.tx.fee_source,
+    };
+
+    let key = match account {
+        MuxedAccount::Ed25519(key) => key.0,
+        MuxedAccount::MuxedEd25519(muxed) => muxed.ed25519.0,
+    };
+
+    stellar_strkey::ed25519::PublicKey(key).to_string()
+}
+
+pub async fn get_tracked_transaction(
+    pool: &PgPool,
+    network_name: &str,
+    hash: &str,
+) -> Result<TrackedTransaction, MyError> {
+
+    sqlx::query_as::<_, TrackedTransaction>("SELECT * FROM transactions WHERE network = $1 AND hash = $2")
+        .bind(network_name)
+        .bind(hash)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| MyError::TransactionNotFound(hash.to_string()))
+}
+
+// Handler to submit any signed transaction envelope and track it until it's final
+// Poll /tx/:hash for the outcome
+pub async fn submit_signed_transaction(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Json(signed): Json<SignedTransaction>,
+) -> Result<Json<TrackedTransaction>, MyError> {
+
+    let envelope = decode_envelope(&signed.transaction_xdr)?;
+
+    let transaction = submit_transaction(
+        &state.ttl_pool,
+        &network,
+        &envelope,
+        SUBMITTED_KIND,
+        &fee_source_account(&envelope)
+    )
+    .await?;
+
+    track_transaction(state.ttl_pool.clone(), network.clone(), transaction.hash.clone());
+
+    Ok(Json(transaction))
+}
+
+// Handler to get the status of a transaction submitted through the API
+pub async fn get_transaction_status(
+    State(state): State<Arc<AppState>>,
+    SelectedNetwork(network): SelectedNetwork,
+    Path(hash): Path<String>,
+) -> Result<Json<TrackedTransaction>, MyError> {
+
+    let transaction = get_tracked_transaction(&state.ttl_pool, &network.name, &hash.to_lowercase()).await?;
+
+    Ok(Json(transaction))
+}
@@ -1,4 +1,4 @@
-use crate::AppState;
+use crate::network_profile::{NetworkProfile, DEFAULT_NETWORK_NAME};
 use tokio::time::{Duration, sleep};
 use std::sync::Arc;
 use std::{io::Write, fs::File};
@@ -14,7 +14,7 @@ use graphql_client::{GraphQLQuery, Response as GraphQLResponse};
 pub struct NewJwtToken;
 
 // Function to automatically update the Mercury token access every 7 days
-pub async fn renew_jwt_cron_job(state: Arc<AppState>) {
+pub async fn renew_jwt_cron_job(network: Arc<NetworkProfile>) {
     
     tokio::spawn(async move {
 
@@ -23,8 +23,8 @@ pub async fn renew_jwt_cron_job(state: Arc<AppState>) {
 
         // GraphQL message variables
         let variables = new_jwt_token::Variables {
-            email: state.mercury_id.clone(),
-            password: state.mercury_pwd.clone(),
+            email: network.mercury_id.clone(),
+            password: network.mercury_pwd.clone(),
         };
 
         // Build the GraphQL request body
@@ -35,12 +35,12 @@ pub async fn renew_jwt_cron_job(state: Arc<AppState>) {
         // Loop to continuously renew the JWT token
         loop {
 
-            tracing::debug!("Updating Mercury JWT token");
+            tracing::debug!("Updating Mercury JWT token of network {}", network.name);
             
             // Send the GraphQL mutation to update the token
             // If it returns an error, retry after 10 seconds, otherwise after 7 days
             match client
-                    .post(format!("{}/graphql", state.mercury_graphql_endpoint))
+                    .post(format!("{}/graphql", network.mercury_graphql_endpoint))
                     .json(&request_body)
                     .send()
                     .await {
@@ -56,15 +56,15 @@ pub async fn renew_jwt_cron_job(state: Arc<AppState>) {
                                 .unwrap()
                                 .jwt_token
                                 .unwrap();
-                            let mut file = File::create("./mercury-access-token.txt").unwrap();
+                            let mut file = File::create(token_file_path(&network.name)).unwrap();
                             file.write_all(new_token.as_bytes()).unwrap();
-                            *state.mercury_jwt_token.lock().unwrap() = new_token;
+                            *network.mercury_jwt_token.lock().unwrap() = new_token;
                             time_to_sleep = Duration::from_secs(59 * 60 * 24 * 7); // ~7 days
                         },
                         
                         // Print an error message and try again in 10 seconds
                         Err(e) => {
-                            tracing::error!("Error while updating Mercury JWT token: {:?}", e);
+                            tracing::error!("Error while updating Mercury JWT token of network {}: {:?}", network.name, e);
                             time_to_sleep = Duration::from_secs(10); // 10 seconds
                         }
             };
@@ -73,4 +73,13 @@ pub async fn renew_jwt_cron_job(state: Arc<AppState>) {
             sleep(time_to_sleep).await;
         }
     });
-}
\ No newline at end of file
+}
+
+// The default network keeps the file of the single network deployments
+fn token_file_path(network_name: &str) -> String {
+    if network_name == DEFAULT_NETWORK_NAME {
+        "./mercury-access-token.txt".to_string()
+    } else {
+        format!("./mercury-access-token-{}.txt", network_name)
+    }
+}
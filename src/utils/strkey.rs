@@ -0,0 +1,65 @@
+use crate::error::MyError;
+use axum::extract::Path;
+use axum::Json;
+use serde::Serialize;
+
+// Handler Response: both representations of a contract id
+#[derive(Serialize)]
+pub struct ContractIdFormats {
+    // C... strkey
+    pub contract_id: String,
+    // Hex encoded 32 bytes, as found in the XDR
+    pub hex: String,
+}
+
+// Handler to convert a contract id from its C... strkey to hex, or from hex to its strkey
+pub async fn convert_contract_id(
+    Path(id): Path<String>,
+) -> Result<Json<ContractIdFormats>, MyError> {
+
+    Ok(Json(contract_id_formats(id)?))
+}
+
+fn contract_id_formats(id: String) -> Result<ContractIdFormats, MyError> {
+
+    let contract = if id.starts_with('C') {
+        stellar_strkey::Contract::from_string(&id)
+            .map_err(|e| MyError::StringToContractConversionFailed(id.clone(), e))?
+    } else {
+        let bytes = hex::decode(&id)
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .ok_or_else(|| MyError::InvalidArgument(format!("{} is neither a C... contract id nor 32 hex encoded bytes", id)))?;
+        stellar_strkey::Contract(bytes)
+    };
+
+    Ok(ContractIdFormats {
+        contract_id: contract.to_string(),
+        hex: hex::encode(contract.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_both_ways() {
+        let contract_id = stellar_strkey::Contract([0xab; 32]).to_string();
+
+        let from_strkey = contract_id_formats(contract_id.clone()).ok().unwrap();
+        assert_eq!(from_strkey.hex, "ab".repeat(32));
+        assert_eq!(from_strkey.contract_id, contract_id);
+
+        let from_hex = contract_id_formats("ab".repeat(32)).ok().unwrap();
+        assert_eq!(from_hex.contract_id, contract_id);
+        assert_eq!(from_hex.hex, "ab".repeat(32));
+    }
+
+    #[test]
+    fn rejects_invalid_ids() {
+        assert!(contract_id_formats("CABC".to_string()).is_err());
+        assert!(contract_id_formats("ab".repeat(31)).is_err());
+        assert!(contract_id_formats("zz".repeat(32)).is_err());
+    }
+}
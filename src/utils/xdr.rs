@@ -0,0 +1,74 @@
+use crate::error::MyError;
+use crate::expiration::ledger_key::{hashed_ledger_key_xdr, LedgerKeyQuery};
+use axum::extract::{Path, Query};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use stellar_xdr::curr as stellar_xdr;
+use stellar_xdr::{Limits, ReadXdr, ScVal, WriteXdr};
+
+#[derive(Serialize, Deserialize)]
+pub struct EncodedXdr {
+    // Base64 XDR
+    pub xdr: String,
+}
+
+// Handler Response: ScVal with its base64 XDR
+#[derive(Serialize)]
+pub struct ScValXdr {
+    pub scval: ScVal,
+    pub xdr: String,
+}
+
+// Handler Response: ledger key of a contract entry and its hash, as subscribed to in Mercury
+#[derive(Serialize)]
+pub struct ContractLedgerKey {
+    pub contract_id: String,
+    pub ledger_key_xdr: String,
+    pub hashed_ledger_key_xdr: String,
+}
+
+// Handler to encode an ScVal from its JSON representation, e.g. {"symbol": "deploy"}
+// The XDR can be used as a topic filter of the event subscriptions
+pub async fn encode_scval(
+    Json(scval): Json<ScVal>,
+) -> Result<Json<ScValXdr>, MyError> {
+
+    let xdr = scval
+        .to_xdr_base64(Limits::none())
+        .map_err(MyError::ToXdrError)?;
+
+    Ok(Json(ScValXdr { scval, xdr }))
+}
+
+// Handler to decode a base64 XDR ScVal to JSON
+pub async fn decode_scval(
+    Json(encoded): Json<EncodedXdr>,
+) -> Result<Json<ScValXdr>, MyError> {
+
+    let scval = ScVal::from_xdr_base64(&encoded.xdr, Limits::none())
+        .map_err(MyError::FromXdrError)?;
+
+    Ok(Json(ScValXdr { scval, xdr: encoded.xdr }))
+}
+
+// Handler to build the ledger key of a contract entry (the instance by default) and hash it
+// e.g. /xdr/ledger_key/:id?key=COUNTER&durability=temporary
+pub async fn build_ledger_key(
+    Path(id): Path<String>,
+    Query(ledger_key_query): Query<LedgerKeyQuery>,
+) -> Result<Json<ContractLedgerKey>, MyError> {
+
+    stellar_strkey::Contract::from_string(&id)
+        .map_err(|e| MyError::StringToContractConversionFailed(id.clone(), e))?;
+
+    let ledger_key = ledger_key_query
+        .key_args(id.clone())?
+        .parse_keys()?
+        .remove(0);
+
+    Ok(Json(ContractLedgerKey {
+        contract_id: id,
+        ledger_key_xdr: ledger_key.to_xdr_base64(Limits::none()).map_err(MyError::ToXdrError)?,
+        hashed_ledger_key_xdr: hashed_ledger_key_xdr(&ledger_key)?,
+    }))
+}